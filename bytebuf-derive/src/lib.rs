@@ -0,0 +1,505 @@
+//! `#[derive(Encode, Decode)]` for structs and enums written to a `bytebuf_rs::bytebuf::ByteBuf`.
+//!
+//! Fields are written in declaration order with the buffer's own `write_*`
+//! methods and read back with the fallible `try_read_*` ones:
+//!
+//! * integers and floats are big endian unless `#[bytebuf(le)]` is given
+//! * `bool` is one byte, `[u8; N]` is written as is, other arrays element by element
+//! * `String`, `Vec<u8>` and `Vec<T>` carry a length prefix, `u32_be` unless
+//!   `#[bytebuf(len = "u8" | "u16_be" | "u16_le" | "u32_be" | "u32_le" | "u64_be" | "u64_le" | "varint")]`;
+//!   a `Vec<T>` may not claim more elements than there are bytes left, or than
+//!   `codec::MAX_ZERO_WIDTH_LEN` when that is more
+//! * `Option<T>` is a `u8` tag followed by the value when present
+//! * `#[bytebuf(skip)]` leaves a field out and decodes it as `Default::default()`,
+//!   `#[bytebuf(default = "path::to::fn")]` uses the given function instead
+//! * enums start with their discriminant, a `u8` unless
+//!   `#[bytebuf(tag = "u8" | "u16_be" | "u16_le" | "u32_be" | "u32_le" | "varint")]`
+//!
+//! `le`/`be` and `len` may also be put on the container to change the default
+//! for all of its fields. Any other field type must implement `Encode`/`Decode` itself,
+//! and every type parameter of the container is required to as well.
+
+extern crate proc_macro;
+
+use proc_macro::TokenStream;
+use proc_macro2::{Literal, Span, TokenStream as TokenStream2};
+use quote::{format_ident, quote};
+use syn::{parse_macro_input, parse_quote, Attribute, Data, DeriveInput, Error, Expr, ExprLit, Fields,
+          GenericArgument, Generics, Ident, Lit, LitStr, PathArguments, Result, Type};
+
+#[proc_macro_derive(Encode, attributes(bytebuf))]
+pub fn derive_encode(input: TokenStream) -> TokenStream {
+    let input = parse_macro_input!(input as DeriveInput);
+    expand_encode(&input).unwrap_or_else(Error::into_compile_error).into()
+}
+
+#[proc_macro_derive(Decode, attributes(bytebuf))]
+pub fn derive_decode(input: TokenStream) -> TokenStream {
+    let input = parse_macro_input!(input as DeriveInput);
+    expand_decode(&input).unwrap_or_else(Error::into_compile_error).into()
+}
+
+
+// attributes
+
+#[derive(Clone)]
+struct Attrs {
+    little_endian: bool,
+    len: TokenStream2,
+    tag: String,
+    skip: bool,
+    default: Option<syn::Path>,
+}
+
+impl Default for Attrs {
+    fn default() -> Self {
+        Attrs {
+            little_endian: false,
+            len: quote!(::bytebuf_rs::bytebuf::LengthPrefix::U32Be),
+            tag: "u8".to_string(),
+            skip: false,
+            default: None,
+        }
+    }
+}
+
+fn parse_len(lit: &LitStr) -> Result<TokenStream2> {
+    let variant = match lit.value().as_str() {
+        "varint" => "Varint",
+        "u8" => "U8",
+        "u16_be" => "U16Be",
+        "u16_le" => "U16Le",
+        "u32_be" => "U32Be",
+        "u32_le" => "U32Le",
+        "u64_be" => "U64Be",
+        "u64_le" => "U64Le",
+        _ => return Err(Error::new(lit.span(), "unknown length prefix")),
+    };
+    let variant = Ident::new(variant, lit.span());
+    Ok(quote!(::bytebuf_rs::bytebuf::LengthPrefix::#variant))
+}
+
+fn parse_attrs(attrs: &[Attribute], inherited: &Attrs) -> Result<Attrs> {
+    let mut out = inherited.clone();
+    out.skip = false;
+    out.default = None;
+    for attr in attrs.iter().filter(|a| a.path().is_ident("bytebuf")) {
+        attr.parse_nested_meta(|meta| {
+            if meta.path.is_ident("le") {
+                out.little_endian = true;
+            } else if meta.path.is_ident("be") {
+                out.little_endian = false;
+            } else if meta.path.is_ident("skip") {
+                out.skip = true;
+            } else if meta.path.is_ident("len") {
+                out.len = parse_len(&meta.value()?.parse()?)?;
+            } else if meta.path.is_ident("tag") {
+                let lit: LitStr = meta.value()?.parse()?;
+                match lit.value().as_str() {
+                    "u8" | "u16_be" | "u16_le" | "u32_be" | "u32_le" | "varint" => out.tag = lit.value(),
+                    _ => return Err(meta.error("unknown tag width")),
+                }
+            } else if meta.path.is_ident("default") {
+                let lit: LitStr = meta.value()?.parse()?;
+                out.default = Some(lit.parse()?);
+            } else {
+                return Err(meta.error("unknown bytebuf attribute"));
+            }
+            Ok(())
+        })?;
+    }
+    Ok(out)
+}
+
+
+// field types
+
+enum Kind<'a> {
+    Bool,
+    Byte(&'static str),
+    Number(String),
+    String,
+    Bytes,
+    Vec(&'a Type),
+    Option(&'a Type),
+    ByteArray(&'a Expr),
+    Array(&'a Type, &'a Expr),
+    Other,
+}
+
+fn single_generic(args: &PathArguments) -> Option<&Type> {
+    match args {
+        PathArguments::AngleBracketed(a) if a.args.len() == 1 => match a.args.first() {
+            Some(GenericArgument::Type(t)) => Some(t),
+            _ => None,
+        },
+        _ => None,
+    }
+}
+
+fn is_u8(ty: &Type) -> bool {
+    matches!(ty, Type::Path(p) if p.qself.is_none() && p.path.is_ident("u8"))
+}
+
+fn classify(ty: &Type) -> Kind<'_> {
+    match ty {
+        Type::Array(a) if is_u8(&a.elem) => Kind::ByteArray(&a.len),
+        Type::Array(a) => Kind::Array(&a.elem, &a.len),
+        Type::Path(p) if p.qself.is_none() => {
+            let seg = match p.path.segments.last() {
+                Some(seg) => seg,
+                None => return Kind::Other,
+            };
+            let name = seg.ident.to_string();
+            match name.as_str() {
+                "bool" => Kind::Bool,
+                "u8" => Kind::Byte("u8"),
+                "i8" => Kind::Byte("i8"),
+                "u16" | "i16" | "u32" | "i32" | "u64" | "i64" | "u128" | "i128" | "f32" | "f64" => Kind::Number(name),
+                "String" => Kind::String,
+                "Vec" => match single_generic(&seg.arguments) {
+                    Some(t) if is_u8(t) => Kind::Bytes,
+                    Some(t) => Kind::Vec(t),
+                    None => Kind::Other,
+                },
+                "Option" => match single_generic(&seg.arguments) {
+                    Some(t) => Kind::Option(t),
+                    None => Kind::Other,
+                },
+                _ => Kind::Other,
+            }
+        }
+        _ => Kind::Other,
+    }
+}
+
+fn endian(attrs: &Attrs) -> &'static str {
+    if attrs.little_endian { "le" } else { "be" }
+}
+
+// `v` is a reference to the value being written
+fn encode_value(ty: &Type, v: &TokenStream2, attrs: &Attrs) -> TokenStream2 {
+    let len = &attrs.len;
+    match classify(ty) {
+        Kind::Bool => quote!(buf.write_bool(*#v)?;),
+        Kind::Byte(t) => {
+            let m = format_ident!("write_{}_be", t);
+            quote!(buf.#m(*#v)?;)
+        }
+        Kind::Number(t) => {
+            let m = format_ident!("write_{}_{}", t, endian(attrs));
+            quote!(buf.#m(*#v)?;)
+        }
+        Kind::String => quote!(buf.write_string_with_len(#len, #v)?;),
+        Kind::Bytes => quote!(buf.write_bytes_with_len(#len, #v)?;),
+        Kind::ByteArray(_) => quote!(buf.write_bytes(&#v[..])?;),
+        Kind::Array(elem, _) => {
+            let inner = encode_value(elem, &quote!(e), attrs);
+            quote!(for e in #v.iter() { #inner })
+        }
+        Kind::Vec(elem) => {
+            let inner = encode_value(elem, &quote!(e), attrs);
+            quote! {
+                buf.write_len(#len, #v.len())?;
+                for e in #v.iter() { #inner }
+            }
+        }
+        Kind::Option(inner_ty) => {
+            let inner = encode_value(inner_ty, &quote!(e), attrs);
+            quote! {
+                match #v {
+                    ::std::option::Option::Some(e) => {
+                        buf.write_u8_be(1)?;
+                        #inner
+                    }
+                    ::std::option::Option::None => {
+                        buf.write_u8_be(0)?;
+                    }
+                }
+            }
+        }
+        Kind::Other => quote!(::bytebuf_rs::codec::Encode::encode(#v, buf)?;),
+    }
+}
+
+// evaluates to the decoded value; errors return from the enclosing closure
+fn decode_value(ty: &Type, attrs: &Attrs) -> TokenStream2 {
+    let len = &attrs.len;
+    match classify(ty) {
+        Kind::Bool => quote!(buf.try_read_bool()?),
+        Kind::Byte(t) => {
+            let m = format_ident!("try_read_{}", t);
+            quote!(buf.#m()?)
+        }
+        Kind::Number(t) => {
+            let m = format_ident!("try_read_{}_{}", t, endian(attrs));
+            quote!(buf.#m()?)
+        }
+        Kind::String => quote!(buf.read_string_with_len_strict(#len)?),
+        Kind::Bytes => quote!(buf.read_bytes_with_len(#len)?),
+        Kind::ByteArray(n) => quote!({
+            let mut a = [0u8; #n];
+            buf.try_read_bytes(&mut a)?;
+            a
+        }),
+        Kind::Array(elem, n) => {
+            let inner = decode_value(elem, attrs);
+            quote!({
+                let mut v = ::std::vec::Vec::with_capacity(#n);
+                for _ in 0..#n {
+                    v.push(#inner);
+                }
+                match ::std::convert::TryFrom::try_from(v) {
+                    ::std::result::Result::Ok(a) => a,
+                    ::std::result::Result::Err(_) => ::std::unreachable!(),
+                }
+            })
+        }
+        Kind::Vec(elem) => {
+            let inner = decode_value(elem, attrs);
+            quote!({
+                let n = buf.read_len(#len)?;
+                // elements that take up bytes run out of them, zero-width ones would not
+                let max = ::std::cmp::max(buf.readable_bytes(), ::bytebuf_rs::codec::MAX_ZERO_WIDTH_LEN);
+                if n > max {
+                    return ::std::result::Result::Err(
+                        ::bytebuf_rs::error::ByteBufError::LengthPrefixOverflow { len: n, max });
+                }
+                let mut v = ::std::vec::Vec::new();
+                for _ in 0..n {
+                    v.push(#inner);
+                }
+                v
+            })
+        }
+        Kind::Option(inner_ty) => {
+            let inner = decode_value(inner_ty, attrs);
+            quote!(match buf.try_read_u8()? {
+                0 => ::std::option::Option::None,
+                1 => ::std::option::Option::Some(#inner),
+                t => return ::std::result::Result::Err(::bytebuf_rs::error::ByteBufError::Custom(
+                    ::std::format!("invalid option tag {}", t))),
+            })
+        }
+        Kind::Other => quote!(<#ty as ::bytebuf_rs::codec::Decode>::decode(buf)?),
+    }
+}
+
+fn decode_field(ty: &Type, attrs: &Attrs) -> TokenStream2 {
+    if let Some(path) = &attrs.default {
+        quote!(#path())
+    } else if attrs.skip {
+        quote!(::std::default::Default::default())
+    } else {
+        decode_value(ty, attrs)
+    }
+}
+
+fn binding(i: usize, field: &syn::Field) -> Ident {
+    match &field.ident {
+        Some(ident) => format_ident!("f_{}", ident),
+        None => format_ident!("f_{}", i),
+    }
+}
+
+// writes every field, each already bound by reference to its `binding` name
+fn encode_fields(fields: &Fields, container: &Attrs) -> Result<TokenStream2> {
+    let mut out = TokenStream2::new();
+    for (i, field) in fields.iter().enumerate() {
+        let attrs = parse_attrs(&field.attrs, container)?;
+        if attrs.skip || attrs.default.is_some() {
+            continue;
+        }
+        let name = binding(i, field);
+        out.extend(encode_value(&field.ty, &quote!(#name), &attrs));
+    }
+    Ok(out)
+}
+
+// pattern binding every field by its `binding` name
+fn fields_pattern(fields: &Fields) -> TokenStream2 {
+    let names = fields.iter().enumerate().map(|(i, f)| binding(i, f));
+    match fields {
+        Fields::Named(named) => {
+            let idents = named.named.iter().map(|f| &f.ident);
+            quote!({ #(#idents: #names),* })
+        }
+        Fields::Unnamed(_) => quote!(( #(#names),* )),
+        Fields::Unit => quote!(),
+    }
+}
+
+// constructor body decoding every field in order
+fn decode_fields(fields: &Fields, container: &Attrs) -> Result<TokenStream2> {
+    let mut values = Vec::new();
+    for field in fields.iter() {
+        let attrs = parse_attrs(&field.attrs, container)?;
+        values.push(decode_field(&field.ty, &attrs));
+    }
+    Ok(match fields {
+        Fields::Named(named) => {
+            let idents = named.named.iter().map(|f| &f.ident);
+            quote!({ #(#idents: #values),* })
+        }
+        Fields::Unnamed(_) => quote!(( #(#values),* )),
+        Fields::Unit => quote!(),
+    })
+}
+
+// the discriminant of every variant, each checked to fit in the tag
+fn discriminants(data: &syn::DataEnum, tag: &str) -> Result<Vec<u64>> {
+    let max = match tag {
+        "u8" => u8::MAX as u64,
+        "u16_be" | "u16_le" => u16::MAX as u64,
+        _ => u32::MAX as u64,
+    };
+    let mut next = 0u64;
+    let mut out = Vec::new();
+    for variant in data.variants.iter() {
+        if let Some((_, expr)) = &variant.discriminant {
+            next = match expr {
+                Expr::Lit(ExprLit { lit: Lit::Int(i), .. }) => i.base10_parse()?,
+                _ => return Err(Error::new_spanned(expr, "only integer literal discriminants are supported")),
+            };
+        }
+        if next > max {
+            let message = format!("discriminant {} does not fit in a {} tag", next, tag);
+            return Err(match &variant.discriminant {
+                Some((_, expr)) => Error::new_spanned(expr, message),
+                None => Error::new_spanned(&variant.ident, message),
+            });
+        }
+        out.push(next);
+        next += 1;
+    }
+    Ok(out)
+}
+
+// requires every type parameter to implement `bound`
+fn add_bounds(generics: &Generics, bound: TokenStream2) -> Generics {
+    let mut generics = generics.clone();
+    let params: Vec<Ident> = generics.type_params().map(|p| p.ident.clone()).collect();
+    let where_clause = generics.make_where_clause();
+    for param in params {
+        where_clause.predicates.push(parse_quote!(#param: #bound));
+    }
+    generics
+}
+
+fn write_tag(tag: &str, value: u64) -> TokenStream2 {
+    let value = Literal::u64_unsuffixed(value);
+    match tag {
+        "varint" => quote!(buf.write_varint_u32(#value)?;),
+        "u8" => quote!(buf.write_u8_be(#value)?;),
+        t => {
+            let m = format_ident!("write_{}", t);
+            quote!(buf.#m(#value)?;)
+        }
+    }
+}
+
+fn read_tag(tag: &str) -> TokenStream2 {
+    match tag {
+        "varint" => quote!(buf.try_read_varint_u32()? as u64),
+        "u8" => quote!(buf.try_read_u8()? as u64),
+        t => {
+            let m = format_ident!("try_read_{}", t);
+            quote!(buf.#m()? as u64)
+        }
+    }
+}
+
+fn expand_encode(input: &DeriveInput) -> Result<TokenStream2> {
+    let name = &input.ident;
+    let container = parse_attrs(&input.attrs, &Attrs::default())?;
+    let body = match &input.data {
+        Data::Struct(data) => {
+            let pattern = fields_pattern(&data.fields);
+            let fields = encode_fields(&data.fields, &container)?;
+            quote! {
+                let #name #pattern = self;
+                #fields
+            }
+        }
+        Data::Enum(data) => {
+            let mut arms = Vec::new();
+            for (variant, value) in data.variants.iter().zip(discriminants(data, &container.tag)?) {
+                let ident = &variant.ident;
+                let pattern = fields_pattern(&variant.fields);
+                let tag = write_tag(&container.tag, value);
+                let fields = encode_fields(&variant.fields, &container)?;
+                arms.push(quote! {
+                    #name::#ident #pattern => {
+                        #tag
+                        #fields
+                    }
+                });
+            }
+            quote! {
+                match self {
+                    #(#arms)*
+                }
+            }
+        }
+        Data::Union(_) => return Err(Error::new(Span::call_site(), "unions can not derive Encode")),
+    };
+    let generics = add_bounds(&input.generics, quote!(::bytebuf_rs::codec::Encode));
+    let (impl_generics, ty_generics, where_clause) = generics.split_for_impl();
+    Ok(quote! {
+        impl #impl_generics ::bytebuf_rs::codec::Encode for #name #ty_generics #where_clause {
+            #[allow(unused_variables)]
+            fn encode(&self, buf: &mut ::bytebuf_rs::bytebuf::ByteBuf) -> ::bytebuf_rs::bytebuf::ByteReult<()> {
+                #body
+                ::std::result::Result::Ok(())
+            }
+        }
+    })
+}
+
+fn expand_decode(input: &DeriveInput) -> Result<TokenStream2> {
+    let name = &input.ident;
+    let container = parse_attrs(&input.attrs, &Attrs::default())?;
+    let body = match &input.data {
+        Data::Struct(data) => {
+            let fields = decode_fields(&data.fields, &container)?;
+            quote!(::std::result::Result::Ok(#name #fields))
+        }
+        Data::Enum(data) => {
+            let mut arms = Vec::new();
+            for (variant, value) in data.variants.iter().zip(discriminants(data, &container.tag)?) {
+                let ident = &variant.ident;
+                let value = Literal::u64_unsuffixed(value);
+                let fields = decode_fields(&variant.fields, &container)?;
+                arms.push(quote!(#value => ::std::result::Result::Ok(#name::#ident #fields),));
+            }
+            let tag = read_tag(&container.tag);
+            let type_name = name.to_string();
+            quote! {
+                match #tag {
+                    #(#arms)*
+                    t => ::std::result::Result::Err(::bytebuf_rs::error::ByteBufError::Custom(
+                        ::std::format!("unknown discriminant {} for {}", t, #type_name))),
+                }
+            }
+        }
+        Data::Union(_) => return Err(Error::new(Span::call_site(), "unions can not derive Decode")),
+    };
+    let generics = add_bounds(&input.generics, quote!(::bytebuf_rs::codec::Decode));
+    let (impl_generics, ty_generics, where_clause) = generics.split_for_impl();
+    Ok(quote! {
+        impl #impl_generics ::bytebuf_rs::codec::Decode for #name #ty_generics #where_clause {
+            fn decode(buf: &mut ::bytebuf_rs::bytebuf::ByteBuf) -> ::bytebuf_rs::bytebuf::ByteReult<Self> {
+                // rewind on failure so a partial frame can be retried once more bytes arrive
+                let start = buf.get_reader_index();
+                let decode = |buf: &mut ::bytebuf_rs::bytebuf::ByteBuf| -> ::bytebuf_rs::bytebuf::ByteReult<Self> {
+                    #body
+                };
+                let result = decode(buf);
+                if result.is_err() {
+                    buf.set_reader_index(start);
+                }
+                result
+            }
+        }
+    })
+}
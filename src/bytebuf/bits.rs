@@ -0,0 +1,237 @@
+use std::convert::TryFrom;
+
+use crate::bytebuf::{ByteBuf, ByteReult};
+use crate::error::ByteBufError;
+
+/// The order in which the bits of a byte are handed out.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum BitOrder {
+    /// high bit first, fields read as written left to right (H.264, MQTT)
+    #[default]
+    MsbFirst,
+    /// low bit first, a field's low bit coming first (DEFLATE)
+    LsbFirst,
+}
+
+fn mask(n: u32) -> u64 {
+    if n >= 64 { u64::MAX } else { (1 << n) - 1 }
+}
+
+impl ByteBuf {
+    pub fn bit_reader(&mut self, order: BitOrder) -> BitReader<'_> {
+        BitReader::new(self, order)
+    }
+
+    pub fn bit_writer(&mut self, order: BitOrder) -> BitWriter<'_> {
+        BitWriter::new(self, order)
+    }
+}
+
+/// Reads fields of any width up to 64 bits from the readable bytes.
+///
+/// A byte stays at the reader index until all of its bits are read, so a reader
+/// dropped halfway through a byte leaves that byte readable; `align_to_byte`
+/// skips the rest of it first.
+pub struct BitReader<'a> {
+    buf: &'a mut ByteBuf,
+    order: BitOrder,
+    // bits already read from the byte at the reader index
+    bit_offset: u32,
+}
+
+impl<'a> BitReader<'a> {
+    pub fn new(buf: &'a mut ByteBuf, order: BitOrder) -> Self {
+        BitReader { buf, order, bit_offset: 0 }
+    }
+
+    pub fn order(&self) -> BitOrder {
+        self.order
+    }
+
+    /// The position of the next bit, counted in bits from the start of the buffer.
+    pub fn bit_position(&self) -> usize {
+        self.buf.read_index * 8 + self.bit_offset as usize
+    }
+
+    pub fn bits_remaining(&self) -> usize {
+        self.buf.readable_bytes() * 8 - self.bit_offset as usize
+    }
+
+    pub fn is_aligned(&self) -> bool {
+        self.bit_offset == 0
+    }
+
+    /// Reads an `n` bit field, `n` being at most 64.
+    pub fn read_bits(&mut self, n: u32) -> ByteReult<u64> {
+        assert!(n <= 64, "can not read more than 64 bits at once");
+        if n as usize > self.bits_remaining() {
+            let needed = (self.bit_offset + n).div_ceil(8) as usize;
+            return Err(ByteBufError::InsufficientBytes { needed, available: self.buf.readable_bytes() });
+        }
+        let (mut v, mut done) = (0u64, 0);
+        while done < n {
+            let byte = self.buf.buf[self.buf.read_index] as u64;
+            let take = (8 - self.bit_offset).min(n - done);
+            match self.order {
+                BitOrder::MsbFirst => {
+                    v = (v << take) | ((byte >> (8 - self.bit_offset - take)) & mask(take));
+                }
+                BitOrder::LsbFirst => v |= ((byte >> self.bit_offset) & mask(take)) << done,
+            }
+            done += take;
+            self.bit_offset += take;
+            if self.bit_offset == 8 {
+                self.buf.read_index += 1;
+                self.bit_offset = 0;
+            }
+        }
+        Ok(v)
+    }
+
+    pub fn read_bit(&mut self) -> ByteReult<bool> {
+        Ok(self.read_bits(1)? == 1)
+    }
+
+    /// Skips the rest of a partly read byte and returns how many bits that was.
+    pub fn align_to_byte(&mut self) -> u32 {
+        if self.bit_offset == 0 {
+            return 0;
+        }
+        let skipped = 8 - self.bit_offset;
+        self.buf.read_index += 1;
+        self.bit_offset = 0;
+        skipped
+    }
+
+    /// Reads an unsigned Exp-Golomb code, `ue(v)` in H.264.
+    pub fn read_ue(&mut self) -> ByteReult<u64> {
+        let offset = self.buf.read_index;
+        let k = self.read_exp_golomb()?;
+        u64::try_from(k).map_err(|_| ByteBufError::VarintOverflow { offset })
+    }
+
+    /// Reads a signed Exp-Golomb code, `se(v)` in H.264.
+    pub fn read_se(&mut self) -> ByteReult<i64> {
+        let offset = self.buf.read_index;
+        let k = self.read_exp_golomb()? as i128;
+        let v = if k & 1 == 1 { (k + 1) / 2 } else { -(k / 2) };
+        i64::try_from(v).map_err(|_| ByteBufError::VarintOverflow { offset })
+    }
+
+    // reads a code of n zeros, a one and n more bits; nothing is consumed when it fails
+    fn read_exp_golomb(&mut self) -> ByteReult<u128> {
+        let saved = (self.buf.read_index, self.bit_offset);
+        let result = (|| {
+            let mut zeros = 0;
+            while !self.read_bit()? {
+                zeros += 1;
+                // codes for anything past i64 or u64 have more zeros than this
+                if zeros > 64 {
+                    return Err(ByteBufError::VarintOverflow { offset: saved.0 });
+                }
+            }
+            let suffix = self.read_bits(zeros)? as u128;
+            Ok((1u128 << zeros) - 1 + suffix)
+        })();
+        if result.is_err() {
+            self.buf.read_index = saved.0;
+            self.bit_offset = saved.1;
+        }
+        result
+    }
+}
+
+/// Writes fields of any width up to 64 bits after the writer index.
+///
+/// A partly filled byte is written straight away with its unused bits zeroed,
+/// so the buffer always holds everything written so far, padded out to a whole
+/// byte. `align_to_byte` just leaves that padding in place.
+pub struct BitWriter<'a> {
+    buf: &'a mut ByteBuf,
+    order: BitOrder,
+    // bits already used in the byte before the writer index
+    bit_offset: u32,
+}
+
+impl<'a> BitWriter<'a> {
+    pub fn new(buf: &'a mut ByteBuf, order: BitOrder) -> Self {
+        BitWriter { buf, order, bit_offset: 0 }
+    }
+
+    pub fn order(&self) -> BitOrder {
+        self.order
+    }
+
+    /// The position of the next bit, counted in bits from the start of the buffer.
+    pub fn bit_position(&self) -> usize {
+        self.buf.write_index * 8 - self.free_bits() as usize
+    }
+
+    pub fn is_aligned(&self) -> bool {
+        self.bit_offset == 0
+    }
+
+    // unused bits left in the byte before the writer index
+    fn free_bits(&self) -> u32 {
+        if self.bit_offset == 0 { 0 } else { 8 - self.bit_offset }
+    }
+
+    /// Writes the low `n` bits of `v`, `n` being at most 64.
+    pub fn write_bits(&mut self, v: u64, n: u32) -> ByteReult<()> {
+        assert!(n <= 64, "can not write more than 64 bits at once");
+        let v = v & mask(n);
+        let new_bytes = n.saturating_sub(self.free_bits()).div_ceil(8) as usize;
+        self.buf.write_bytes(&[0u8; 9][..new_bytes])?;
+        let first = self.buf.write_index - new_bytes - (self.free_bits() > 0) as usize;
+        let order = self.order;
+        let bytes = self.buf.buf.slice_mut(first..self.buf.write_index, self.buf.capacity);
+        let (mut index, mut done) = (0, 0);
+        while done < n {
+            let take = (8 - self.bit_offset).min(n - done);
+            bytes[index] |= match order {
+                BitOrder::MsbFirst => ((v >> (n - done - take)) & mask(take)) << (8 - self.bit_offset - take),
+                BitOrder::LsbFirst => ((v >> done) & mask(take)) << self.bit_offset,
+            } as u8;
+            done += take;
+            self.bit_offset += take;
+            if self.bit_offset == 8 {
+                index += 1;
+                self.bit_offset = 0;
+            }
+        }
+        Ok(())
+    }
+
+    pub fn write_bit(&mut self, bit: bool) -> ByteReult<()> {
+        self.write_bits(bit as u64, 1)
+    }
+
+    /// Pads a partly filled byte with zero bits and returns how many that took.
+    pub fn align_to_byte(&mut self) -> u32 {
+        let padded = self.free_bits();
+        self.bit_offset = 0;
+        padded
+    }
+
+    /// Writes an unsigned Exp-Golomb code, `ue(v)` in H.264.
+    pub fn write_ue(&mut self, v: u64) -> ByteReult<()> {
+        self.write_exp_golomb(v as u128 + 1)
+    }
+
+    /// Writes a signed Exp-Golomb code, `se(v)` in H.264.
+    pub fn write_se(&mut self, v: i64) -> ByteReult<()> {
+        let v = v as i128;
+        let k = if v > 0 { 2 * v - 1 } else { -2 * v };
+        self.write_exp_golomb(k as u128 + 1)
+    }
+
+    // writes `x`, which is at least one, as its bit length less one zeros followed by `x` itself
+    fn write_exp_golomb(&mut self, x: u128) -> ByteReult<()> {
+        let zeros = 127 - x.leading_zeros();
+        // reserved up front so that a code is never left half written
+        self.buf.ensure_writable((2 * zeros as usize + 1).saturating_sub(self.free_bits() as usize).div_ceil(8))?;
+        self.write_bits(0, zeros)?;
+        self.write_bits(1, 1)?;
+        self.write_bits(x as u64, zeros)
+    }
+}
@@ -0,0 +1,100 @@
+use std::cmp;
+
+use bytes::buf::UninitSlice;
+use bytes::{Buf, BufMut, Bytes, BytesMut};
+
+use crate::bytebuf::ByteBuf;
+
+impl ByteBuf {
+    /// This buffer as a `bytes::Buf` and `BufMut`.
+    pub fn as_buf(&mut self) -> BufAdapter<'_> {
+        BufAdapter { buf: self }
+    }
+}
+
+/// `bytes::Buf` and `BufMut` over a borrowed `ByteBuf`.
+///
+/// The buffer's own `get_*` methods peek at the reader index and follow its
+/// endian, while `Buf`'s advance and read big endian. Keeping the traits on
+/// this wrapper means neither set can shadow the other.
+pub struct BufAdapter<'a> {
+    buf: &'a mut ByteBuf,
+}
+
+impl Buf for BufAdapter<'_> {
+    fn remaining(&self) -> usize {
+        self.buf.readable_bytes()
+    }
+
+    fn chunk(&self) -> &[u8] {
+        &self.buf.buf[self.buf.read_index..self.buf.write_index]
+    }
+
+    fn advance(&mut self, cnt: usize) {
+        let remaining = self.buf.readable_bytes();
+        assert!(cnt <= remaining, "cannot advance past `remaining`: {} <= {}", cnt, remaining);
+        self.buf.read_index += cnt;
+    }
+}
+
+unsafe impl BufMut for BufAdapter<'_> {
+    fn remaining_mut(&self) -> usize {
+        self.buf.max_writable_bytes()
+    }
+
+    unsafe fn advance_mut(&mut self, cnt: usize) {
+        let remaining = self.buf.writable_bytes();
+        assert!(cnt <= remaining, "cannot advance past `remaining_mut`: {:?} <= {:?}", cnt, remaining);
+        self.buf.buf.set_len(self.buf.write_index + cnt);
+        self.buf.write_index += cnt;
+    }
+
+    fn chunk_mut(&mut self) -> &mut UninitSlice {
+        let buf = &mut *self.buf;
+        buf.maybe_discard_read_bytes();
+        if buf.writable_bytes() == 0 {
+            let n = cmp::min(cmp::max(64, buf.write_index), buf.max_writable_bytes());
+            // no room is handed out past max_capacity, where remaining_mut() is 0
+            if buf.ensure_writable(n).is_err() {
+                return UninitSlice::new(&mut []);
+            }
+        }
+        let writable = buf.writable_bytes();
+        UninitSlice::uninit(&mut buf.buf.spare_mut(buf.capacity)[..writable])
+    }
+
+    fn put_slice(&mut self, src: &[u8]) {
+        self.buf.write_bytes(src).unwrap();
+    }
+}
+
+// the conversions below hand the Vec over without copying whenever the
+// storage is uniquely owned
+
+impl From<ByteBuf> for Bytes {
+    fn from(bb: ByteBuf) -> Self {
+        let mut buf = bb.buf.into_vec();
+        buf.truncate(bb.write_index);
+        let mut bytes = Bytes::from(buf);
+        bytes.advance(bb.read_index);
+        bytes
+    }
+}
+
+impl From<ByteBuf> for BytesMut {
+    fn from(bb: ByteBuf) -> Self {
+        BytesMut::from(Bytes::from(bb))
+    }
+}
+
+impl From<Bytes> for ByteBuf {
+    fn from(bytes: Bytes) -> Self {
+        ByteBuf::from(Vec::from(bytes))
+    }
+}
+
+impl From<BytesMut> for ByteBuf {
+    fn from(bytes: BytesMut) -> Self {
+        ByteBuf::from(Vec::from(bytes))
+    }
+}
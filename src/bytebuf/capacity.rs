@@ -0,0 +1,76 @@
+use crate::bytebuf::{ByteBuf, ByteReult, CHUNK_SIZE};
+use crate::error::ByteBufError;
+
+/// How far the capacity grows once a write no longer fits.
+#[derive(Debug, Clone, Copy, Default)]
+pub enum GrowthStrategy {
+    /// double the current capacity
+    #[default]
+    Doubling,
+    /// round the required capacity up to a multiple of `CHUNK_SIZE`
+    Chunked,
+    /// grow to exactly the required capacity
+    Exact,
+    /// called with the current and the required capacity
+    Custom(fn(usize, usize) -> usize),
+}
+
+impl GrowthStrategy {
+    /// The capacity to grow to from `current`, never less than `required`.
+    pub fn grow(&self, current: usize, required: usize) -> usize {
+        let grown = match self {
+            GrowthStrategy::Doubling => current.saturating_mul(2),
+            GrowthStrategy::Chunked => required.div_ceil(CHUNK_SIZE).saturating_mul(CHUNK_SIZE),
+            GrowthStrategy::Exact => required,
+            GrowthStrategy::Custom(f) => f(current, required),
+        };
+        grown.max(required)
+    }
+}
+
+impl ByteBuf {
+    /// Grows the capacity, within `max_capacity`, so that `n` more bytes can be written.
+    pub fn ensure_writable(&mut self, n: usize) -> ByteReult<()> {
+        let required = self.write_index.saturating_add(n);
+        if required <= self.capacity {
+            return Ok(());
+        }
+        if required > self.max_capacity {
+            return Err(ByteBufError::CapacityExceeded { required, max: self.max_capacity });
+        }
+        self.capacity = self.growth.grow(self.capacity, required).min(self.max_capacity);
+        Ok(())
+    }
+
+    /// How many bytes can be written before the buffer has to grow.
+    pub fn writable_bytes(&self) -> usize {
+        self.capacity - self.write_index
+    }
+
+    /// How many bytes can be written before hitting `max_capacity`.
+    pub fn max_writable_bytes(&self) -> usize {
+        self.max_capacity - self.write_index
+    }
+
+    pub fn max_capacity(&self) -> usize {
+        self.max_capacity
+    }
+
+    /// Caps how far the buffer may grow; writes past it fail with `CapacityExceeded`.
+    pub fn set_max_capacity(&mut self, max: usize) -> ByteReult<()> {
+        if max < self.write_index {
+            return Err(ByteBufError::CapacityExceeded { required: self.write_index, max });
+        }
+        self.max_capacity = max;
+        self.capacity = self.capacity.min(max);
+        Ok(())
+    }
+
+    pub fn growth_strategy(&self) -> GrowthStrategy {
+        self.growth
+    }
+
+    pub fn set_growth_strategy(&mut self, growth: GrowthStrategy) {
+        self.growth = growth;
+    }
+}
@@ -0,0 +1,319 @@
+use std::io::{self, Write};
+use std::ops::{Deref, RangeBounds};
+
+use crate::bytebuf::{ByteBuf, ByteOrder, ByteReult, Endian, LengthPrefix, ToBytes};
+use crate::bytebuf::endian::endian_writers;
+use crate::error::ByteBufError;
+
+// table for a reflected crc of up to 32 bits
+const fn reflected_table(poly: u32) -> [u32; 256] {
+    let mut table = [0u32; 256];
+    let mut i = 0;
+    while i < 256 {
+        let mut crc = i as u32;
+        let mut k = 0;
+        while k < 8 {
+            crc = if crc & 1 != 0 { (crc >> 1) ^ poly } else { crc >> 1 };
+            k += 1;
+        }
+        table[i] = crc;
+        i += 1;
+    }
+    table
+}
+
+// table for a msb-first crc-16
+const fn crc16_table(poly: u16) -> [u16; 256] {
+    let mut table = [0u16; 256];
+    let mut i = 0;
+    while i < 256 {
+        let mut crc = (i as u16) << 8;
+        let mut k = 0;
+        while k < 8 {
+            crc = if crc & 0x8000 != 0 { (crc << 1) ^ poly } else { crc << 1 };
+            k += 1;
+        }
+        table[i] = crc;
+        i += 1;
+    }
+    table
+}
+
+static CRC32_TABLE: [u32; 256] = reflected_table(0xEDB8_8320);
+static CRC32C_TABLE: [u32; 256] = reflected_table(0x82F6_3B78);
+static CRC16_MODBUS_TABLE: [u32; 256] = reflected_table(0xA001);
+static CRC16_CCITT_TABLE: [u16; 256] = crc16_table(0x1021);
+
+const ADLER_MOD: u32 = 65521;
+// the most bytes that can be summed before the adler sums have to be reduced
+const ADLER_NMAX: usize = 5552;
+
+/// The CRC-16 flavours.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Crc16 {
+    /// CRC-16/CCITT-FALSE: polynomial 0x1021, initial value 0xFFFF, msb first
+    Ccitt,
+    /// CRC-16/MODBUS: polynomial 0x8005 reflected, initial value 0xFFFF
+    Modbus,
+}
+
+/// The algorithms a `Checksum` can run.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ChecksumKind {
+    /// CRC-32 as in zlib, Ethernet and PNG
+    Crc32,
+    /// CRC-32C (Castagnoli) as in iSCSI and ext4
+    Crc32c,
+    Adler32,
+    Crc16(Crc16),
+}
+
+/// A running checksum, fed with `update` and read with `value`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Checksum {
+    kind: ChecksumKind,
+    state: u32,
+}
+
+impl Checksum {
+    pub fn new(kind: ChecksumKind) -> Self {
+        let state = match kind {
+            ChecksumKind::Crc32 | ChecksumKind::Crc32c => 0xFFFF_FFFF,
+            ChecksumKind::Adler32 => 1,
+            ChecksumKind::Crc16(_) => 0xFFFF,
+        };
+        Checksum { kind, state }
+    }
+
+    pub fn kind(&self) -> ChecksumKind {
+        self.kind
+    }
+
+    pub fn update(&mut self, bytes: &[u8]) {
+        let mut s = self.state;
+        match self.kind {
+            ChecksumKind::Crc32 => {
+                bytes.iter().for_each(|&b| s = (s >> 8) ^ CRC32_TABLE[((s ^ b as u32) & 0xff) as usize]);
+            }
+            ChecksumKind::Crc32c => {
+                bytes.iter().for_each(|&b| s = (s >> 8) ^ CRC32C_TABLE[((s ^ b as u32) & 0xff) as usize]);
+            }
+            ChecksumKind::Crc16(Crc16::Modbus) => {
+                bytes.iter().for_each(|&b| s = (s >> 8) ^ CRC16_MODBUS_TABLE[((s ^ b as u32) & 0xff) as usize]);
+            }
+            ChecksumKind::Crc16(Crc16::Ccitt) => {
+                let mut crc = s as u16;
+                bytes.iter().for_each(|&b| crc = (crc << 8) ^ CRC16_CCITT_TABLE[((crc >> 8) as u8 ^ b) as usize]);
+                s = crc as u32;
+            }
+            ChecksumKind::Adler32 => {
+                let (mut a, mut b) = (s & 0xffff, s >> 16);
+                for chunk in bytes.chunks(ADLER_NMAX) {
+                    for &x in chunk {
+                        a += x as u32;
+                        b += a;
+                    }
+                    a %= ADLER_MOD;
+                    b %= ADLER_MOD;
+                }
+                s = (b << 16) | a;
+            }
+        }
+        self.state = s;
+    }
+
+    /// The checksum of everything fed so far; 16 bit checksums sit in the low half.
+    pub fn value(&self) -> u32 {
+        match self.kind {
+            ChecksumKind::Crc32 | ChecksumKind::Crc32c => !self.state,
+            ChecksumKind::Adler32 | ChecksumKind::Crc16(_) => self.state,
+        }
+    }
+
+    pub fn reset(&mut self) {
+        *self = Checksum::new(self.kind);
+    }
+}
+
+fn checksum_of(kind: ChecksumKind, bytes: &[u8]) -> u32 {
+    let mut checksum = Checksum::new(kind);
+    checksum.update(bytes);
+    checksum.value()
+}
+
+impl ByteBuf {
+    /// The checksum of the readable bytes within `range`.
+    pub fn checksum<R: RangeBounds<usize>>(&self, range: R, kind: ChecksumKind) -> u32 {
+        let range = self.readable_range(range);
+        checksum_of(kind, &self.buf[range])
+    }
+
+    pub fn crc32<R: RangeBounds<usize>>(&self, range: R) -> u32 {
+        self.checksum(range, ChecksumKind::Crc32)
+    }
+
+    pub fn crc32c<R: RangeBounds<usize>>(&self, range: R) -> u32 {
+        self.checksum(range, ChecksumKind::Crc32c)
+    }
+
+    pub fn adler32<R: RangeBounds<usize>>(&self, range: R) -> u32 {
+        self.checksum(range, ChecksumKind::Adler32)
+    }
+
+    pub fn crc16<R: RangeBounds<usize>>(&self, range: R, variant: Crc16) -> u16 {
+        self.checksum(range, ChecksumKind::Crc16(variant)) as u16
+    }
+
+    /// Appends the big endian CRC-32 of the readable bytes and returns it.
+    pub fn write_crc32_be_trailer(&mut self) -> ByteReult<u32> {
+        let crc = self.crc32(..);
+        self.write_u32_be(crc)?;
+        Ok(crc)
+    }
+
+    /// Checks the readable bytes against the big endian CRC-32 in their last four bytes.
+    /// The buffer is left as it is, trailer included.
+    pub fn verify_crc32_trailer(&self) -> ByteReult<()> {
+        self.check_readable(4)?;
+        let end = self.write_index - 4;
+        let bytes = &self.buf[end..self.write_index];
+        let expected = u32::from_be_bytes([bytes[0], bytes[1], bytes[2], bytes[3]]);
+        let actual = self.crc32(..end);
+        if expected != actual {
+            return Err(ByteBufError::ChecksumMismatch { expected, actual });
+        }
+        Ok(())
+    }
+
+    /// A `ChecksumWriter` starting at the current writer index.
+    pub fn checksum_writer(&mut self, kind: ChecksumKind) -> ChecksumWriter<'_> {
+        ChecksumWriter::new(self, kind)
+    }
+}
+
+// declares `write_*` methods that forward to the buffer and fold in what they wrote
+macro_rules! forward_writes {
+    ($($write:ident($($arg:ident: $t:ty),*) -> $r:ty;)*) => {
+        $(
+            pub fn $write(&mut self, $($arg: $t),*) -> $r {
+                self.counted(|buf| buf.$write($($arg),*))
+            }
+        )*
+    };
+}
+
+/// Keeps a running checksum of everything written through it.
+///
+/// Writes go through its own `write_*` methods, which mirror the buffer's, or
+/// through `io::Write`. It only derefs to the buffer for reading, so nothing can
+/// move the writer index behind its back. Automatic discarding of read bytes is
+/// held off while it is alive.
+pub struct ChecksumWriter<'a> {
+    buf: &'a mut ByteBuf,
+    checksum: Checksum,
+    discard_threshold: Option<usize>,
+}
+
+impl<'a> ChecksumWriter<'a> {
+    pub fn new(buf: &'a mut ByteBuf, kind: ChecksumKind) -> Self {
+        let discard_threshold = buf.discard_threshold();
+        buf.set_discard_threshold(None);
+        ChecksumWriter { buf, checksum: Checksum::new(kind), discard_threshold }
+    }
+
+    /// The checksum of everything written so far.
+    pub fn value(&self) -> u32 {
+        self.checksum.value()
+    }
+
+    /// Starts over from the current writer index.
+    pub fn reset(&mut self) {
+        self.checksum.reset();
+    }
+
+    // runs a write and feeds the checksum whatever it appended, even when it failed halfway
+    fn counted<R>(&mut self, write: impl FnOnce(&mut ByteBuf) -> R) -> R {
+        let start = self.buf.write_index;
+        let r = write(self.buf);
+        self.checksum.update(&self.buf.buf[start..self.buf.write_index]);
+        r
+    }
+
+    pub fn write_as<T: ToBytes, E: ByteOrder>(&mut self, v: T) -> io::Result<usize> {
+        self.counted(|buf| buf.write_as::<T, E>(v))
+    }
+
+    pub fn write_with<T: ToBytes>(&mut self, endian: Endian, v: T) -> io::Result<usize> {
+        self.counted(|buf| buf.write_with(endian, v))
+    }
+
+    endian_writers!();
+
+    forward_writes! {
+        write_bytes(bytes: &[u8]) -> io::Result<usize>;
+        write_str(v: &str) -> io::Result<usize>;
+        write_bytebuf(bb: ByteBuf) -> io::Result<usize>;
+        write_hex_str(hex: &str) -> ByteReult<usize>;
+        write_len(prefix: LengthPrefix, len: usize) -> ByteReult<()>;
+        write_bytes_with_len(prefix: LengthPrefix, v: &[u8]) -> ByteReult<usize>;
+        write_string_with_len(prefix: LengthPrefix, v: &str) -> ByteReult<usize>;
+        write_string_with_u8_be_len(v: String) -> ByteReult<usize>;
+        write_string_with_u16_be_len(v: String) -> ByteReult<usize>;
+        write_string_with_u16_le_len(v: String) -> ByteReult<usize>;
+        write_string_with_u32_be_len(v: String) -> ByteReult<usize>;
+        write_string_with_u32_le_len(v: String) -> ByteReult<usize>;
+        write_varint_u32(v: u32) -> io::Result<usize>;
+        write_varint_u64(v: u64) -> io::Result<usize>;
+        write_varint_i32(v: i32) -> io::Result<usize>;
+        write_varint_i64(v: i64) -> io::Result<usize>;
+        write_bool(v: bool) -> io::Result<usize>;
+        write_u8_be(v: u8) -> io::Result<usize>;
+        write_u8_le(v: u8) -> io::Result<usize>;
+        write_i8_be(v: i8) -> io::Result<usize>;
+        write_i8_le(v: i8) -> io::Result<usize>;
+        write_u16_be(v: u16) -> io::Result<usize>;
+        write_u16_le(v: u16) -> io::Result<usize>;
+        write_i16_be(v: i16) -> io::Result<usize>;
+        write_i16_le(v: i16) -> io::Result<usize>;
+        write_u32_be(v: u32) -> io::Result<usize>;
+        write_u32_le(v: u32) -> io::Result<usize>;
+        write_i32_be(v: i32) -> io::Result<usize>;
+        write_i32_le(v: i32) -> io::Result<usize>;
+        write_u64_be(v: u64) -> io::Result<usize>;
+        write_u64_le(v: u64) -> io::Result<usize>;
+        write_i64_be(v: i64) -> io::Result<usize>;
+        write_i64_le(v: i64) -> io::Result<usize>;
+        write_u128_be(v: u128) -> io::Result<usize>;
+        write_u128_le(v: u128) -> io::Result<usize>;
+        write_i128_be(v: i128) -> io::Result<usize>;
+        write_i128_le(v: i128) -> io::Result<usize>;
+        write_f32_be(v: f32) -> io::Result<usize>;
+        write_f32_le(v: f32) -> io::Result<usize>;
+        write_f64_be(v: f64) -> io::Result<usize>;
+        write_f64_le(v: f64) -> io::Result<usize>;
+    }
+}
+
+impl Deref for ChecksumWriter<'_> {
+    type Target = ByteBuf;
+
+    fn deref(&self) -> &ByteBuf {
+        self.buf
+    }
+}
+
+impl Write for ChecksumWriter<'_> {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        self.write_bytes(buf)
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+}
+
+impl Drop for ChecksumWriter<'_> {
+    fn drop(&mut self) {
+        self.buf.set_discard_threshold(self.discard_threshold);
+    }
+}
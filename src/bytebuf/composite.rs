@@ -0,0 +1,380 @@
+use std::borrow::Cow;
+use std::cmp;
+use std::io::{self, IoSlice, Read, Write};
+
+use crate::bytebuf::{BigEndian, ByteBuf, ByteOrder, ByteReult, Endian, FromBytes, LengthPrefix, LittleEndian};
+use crate::bytebuf::cursor::{ByteSource, Cursor};
+use crate::bytebuf::endian::endian_readers;
+use crate::bytebuf::length_prefix::len_readers;
+use crate::bytebuf::utf8::strict_str_readers;
+use crate::bytebuf::varint::varint_readers;
+
+/// Several buffers read as one, without copying them together.
+///
+/// Each component keeps the readable bytes it had when it was added, as a view
+/// sharing that buffer's storage. Reads see the components back to back, and
+/// a value that straddles two of them is read just like any other.
+#[derive(Clone)]
+pub struct CompositeByteBuf {
+    components: Components,
+    read_mark: isize,
+    read_index: usize,
+    write_index: usize,
+    endian: Endian,
+}
+
+// the components with the logical index each one starts at; kept apart from the
+// indices so a read can borrow the bytes while it advances the reader index
+#[derive(Clone, Default)]
+struct Components {
+    bufs: Vec<ByteBuf>,
+    offsets: Vec<usize>,
+}
+
+impl Components {
+    // the component holding logical `index`, with the index relative to that component
+    fn locate(&self, index: usize) -> (usize, usize) {
+        let i = match self.offsets.binary_search(&index) {
+            Ok(i) => i,
+            Err(i) => i - 1,
+        };
+        (i, index - self.offsets[i])
+    }
+}
+
+impl ByteSource for Components {
+    fn contiguous(&self, index: usize, len: usize) -> Option<&[u8]> {
+        if len == 0 {
+            return Some(&[]);
+        }
+        let (i, off) = self.locate(index);
+        self.bufs[i].as_slice().get(off..off + len)
+    }
+
+    fn copy_at(&self, index: usize, out: &mut [u8]) {
+        if out.is_empty() {
+            return;
+        }
+        let (mut i, mut off) = self.locate(index);
+        let mut done = 0;
+        while done < out.len() {
+            let src = &self.bufs[i].as_slice()[off..];
+            let n = cmp::min(src.len(), out.len() - done);
+            out[done..done + n].copy_from_slice(&src[..n]);
+            done += n;
+            i += 1;
+            off = 0;
+        }
+    }
+}
+
+// declares the peeking, reading and checked variants of a fixed width accessor
+macro_rules! accessors {
+    ($($t:ty, $e:ty => $get:ident, $try_get:ident, $read:ident, $try_read:ident;)*) => {
+        $(
+            pub fn $get(&self) -> $t {
+                self.get_as::<$t, $e>()
+            }
+
+            pub fn $try_get(&self) -> ByteReult<$t> {
+                self.try_get_as::<$t, $e>()
+            }
+
+            pub fn $read(&mut self) -> $t {
+                self.read_as::<$t, $e>()
+            }
+
+            pub fn $try_read(&mut self) -> ByteReult<$t> {
+                self.try_read_as::<$t, $e>()
+            }
+        )*
+    };
+}
+
+// declares the lossy readers of a string behind a fixed length prefix
+macro_rules! prefixed_strings {
+    ($($read:ident => $try_read:ident;)*) => {
+        $(
+            pub fn $read(&mut self) -> String {
+                self.$try_read().unwrap()
+            }
+        )*
+    };
+}
+
+impl CompositeByteBuf {
+    pub fn new() -> Self {
+        CompositeByteBuf {
+            components: Components::default(),
+            read_mark: -1,
+            read_index: 0,
+            write_index: 0,
+            endian: Endian::Big,
+        }
+    }
+
+    /// Appends the readable bytes of `buf` after the existing components.
+    pub fn add_component(&mut self, buf: ByteBuf) {
+        if !buf.is_readable() {
+            return;
+        }
+        let component = buf.retained_slice();
+        self.components.offsets.push(self.write_index);
+        self.write_index += component.readable_bytes();
+        self.components.bufs.push(component);
+    }
+
+    pub fn num_components(&self) -> usize {
+        self.components.bufs.len()
+    }
+
+    pub fn component(&self, i: usize) -> Option<&ByteBuf> {
+        self.components.bufs.get(i)
+    }
+
+    /// Drops the components that were read completely, shifting the indices back by their length.
+    pub fn discard_read_components(&mut self) {
+        let read_index = self.read_index;
+        let Components { bufs, offsets } = &mut self.components;
+        let n = offsets.iter().zip(bufs.iter())
+            .take_while(|(offset, c)| *offset + c.readable_bytes() <= read_index)
+            .count();
+        if n == 0 {
+            return;
+        }
+        let shift = offsets.get(n).copied().unwrap_or(self.write_index);
+        bufs.drain(..n);
+        offsets.drain(..n);
+        offsets.iter_mut().for_each(|offset| *offset -= shift);
+        self.read_index -= shift;
+        self.write_index -= shift;
+        if self.read_mark != -1 {
+            self.read_mark = (self.read_mark - shift as isize).max(0);
+        }
+    }
+
+    /// Copies all components into a single one; indices are left as they are.
+    pub fn consolidate(&mut self) {
+        if self.components.bufs.len() < 2 {
+            return;
+        }
+        let mut buf = ByteBuf::new_with_capacity(self.write_index);
+        for c in &self.components.bufs {
+            buf.write_bytes(c.as_slice()).unwrap();
+        }
+        self.components = Components { bufs: vec![buf], offsets: vec![0] };
+    }
+
+    /// Turns this into a plain `ByteBuf` with the same indices, copying only when there is more than one component.
+    pub fn into_bytebuf(mut self) -> ByteBuf {
+        self.consolidate();
+        let mut buf = self.components.bufs.pop().unwrap_or_else(|| ByteBuf::new_with_capacity(0));
+        buf.set_reader_index(self.read_index);
+        buf
+    }
+
+    /// Byte order used by the accessors without a `_be`/`_le`/`_ne` suffix, big endian by default.
+    pub fn endian(&self) -> Endian {
+        self.endian
+    }
+
+    pub fn set_endian(&mut self, endian: Endian) {
+        self.endian = endian;
+    }
+
+    fn native_endian(&self) -> Endian {
+        Endian::native()
+    }
+
+    pub fn readable_bytes(&self) -> usize {
+        self.write_index - self.read_index
+    }
+
+    pub fn is_readable(&self) -> bool {
+        self.readable_bytes() > 0
+    }
+
+    pub fn get_writer_index(&self) -> usize {
+        self.write_index
+    }
+
+    pub fn get_reader_index(&self) -> usize {
+        self.read_index
+    }
+
+    pub fn set_reader_index(&mut self, v: usize) {
+        self.read_index = v;
+    }
+
+    pub fn mark_reader_index(&mut self) {
+        self.read_mark = self.read_index as isize
+    }
+
+    pub fn reset_reader_index(&mut self) {
+        if self.read_mark != -1 {
+            self.read_index = self.read_mark as usize;
+            self.read_mark = -1;
+        }
+    }
+
+    pub fn skip_index(&mut self, n: usize) -> ByteReult<usize> {
+        self.check_readable(n)?;
+        self.read_index += n;
+        Ok(self.read_index)
+    }
+
+    // the readable bytes, for the readers shared with `ByteBuf`
+    fn cursor(&self) -> Cursor<'_, Components> {
+        Cursor::new(&self.components, self.read_index, self.write_index)
+    }
+
+    fn check_readable(&self, len: usize) -> ByteReult<()> {
+        self.cursor().check_readable(len)
+    }
+
+    pub fn try_get_bytes(&self, bytes: &mut [u8]) -> ByteReult<usize> {
+        self.check_readable(bytes.len())?;
+        self.components.copy_at(self.read_index, bytes);
+        Ok(bytes.len())
+    }
+
+    pub fn get_bytes(&self, bytes: &mut [u8]) -> usize {
+        self.try_get_bytes(bytes).unwrap()
+    }
+
+    pub fn try_read_bytes(&mut self, bytes: &mut [u8]) -> ByteReult<usize> {
+        let n = self.try_get_bytes(bytes)?;
+        self.read_index += n;
+        Ok(n)
+    }
+
+    pub fn read_bytes(&mut self, bytes: &mut [u8]) {
+        self.try_read_bytes(bytes).unwrap();
+    }
+
+    /// The next `len` readable bytes as a `ByteBuf`; a view when they sit in one component, a copy otherwise.
+    pub fn read_slice(&mut self, len: usize) -> ByteReult<ByteBuf> {
+        self.check_readable(len)?;
+        if len > 0 {
+            let (i, off) = self.components.locate(self.read_index);
+            if off + len <= self.components.bufs[i].readable_bytes() {
+                self.read_index += len;
+                return self.components.bufs[i].slice(off, len);
+            }
+        }
+        let mut bytes = vec![0u8; len];
+        self.read_bytes(&mut bytes);
+        Ok(ByteBuf::from(bytes))
+    }
+
+    pub fn read_string(&mut self, len: usize) -> String {
+        let mut bytes = vec![0u8; len];
+        self.read_bytes(&mut bytes);
+        String::from_utf8_lossy(&bytes).into_owned()
+    }
+
+    pub fn try_read_string(&mut self, len: usize) -> ByteReult<String> {
+        self.check_readable(len)?;
+        Ok(self.read_string(len))
+    }
+
+    /// The next `len` bytes as a `&str` when they sit in one component, a copy otherwise; advances the reader index.
+    pub fn read_str_ref(&mut self, len: usize) -> ByteReult<Cow<'_, str>> {
+        let s = Cursor::new(&self.components, self.read_index, self.write_index).get_str(0, len)?;
+        self.read_index += len;
+        Ok(s)
+    }
+
+    strict_str_readers!();
+
+    len_readers!();
+
+    prefixed_strings! {
+        read_string_with_u8_be_len => try_read_string_with_u8_be_len;
+        read_string_with_u16_be_len => try_read_string_with_u16_be_len;
+        read_string_with_u16_le_len => try_read_string_with_u16_le_len;
+        read_string_with_u32_be_len => try_read_string_with_u32_be_len;
+        read_string_with_u32_le_len => try_read_string_with_u32_le_len;
+    }
+
+    varint_readers!();
+
+    pub fn try_get_as<T: FromBytes, E: ByteOrder>(&self) -> ByteReult<T> {
+        self.cursor().get_as::<T, E>()
+    }
+
+    pub fn get_as<T: FromBytes, E: ByteOrder>(&self) -> T {
+        self.try_get_as::<T, E>().unwrap()
+    }
+
+    pub fn try_read_as<T: FromBytes, E: ByteOrder>(&mut self) -> ByteReult<T> {
+        let r = self.try_get_as::<T, E>()?;
+        self.read_index += T::SIZE;
+        Ok(r)
+    }
+
+    pub fn read_as<T: FromBytes, E: ByteOrder>(&mut self) -> T {
+        self.try_read_as::<T, E>().unwrap()
+    }
+
+    endian_readers!();
+
+    accessors! {
+        bool, BigEndian => get_bool, try_get_bool, read_bool, try_read_bool;
+        u8, BigEndian => get_u8, try_get_u8, read_u8, try_read_u8;
+        i8, BigEndian => get_i8, try_get_i8, read_i8, try_read_i8;
+        u16, BigEndian => get_u16_be, try_get_u16_be, read_u16_be, try_read_u16_be;
+        u16, LittleEndian => get_u16_le, try_get_u16_le, read_u16_le, try_read_u16_le;
+        i16, BigEndian => get_i16_be, try_get_i16_be, read_i16_be, try_read_i16_be;
+        i16, LittleEndian => get_i16_le, try_get_i16_le, read_i16_le, try_read_i16_le;
+        u32, BigEndian => get_u32_be, try_get_u32_be, read_u32_be, try_read_u32_be;
+        u32, LittleEndian => get_u32_le, try_get_u32_le, read_u32_le, try_read_u32_le;
+        i32, BigEndian => get_i32_be, try_get_i32_be, read_i32_be, try_read_i32_be;
+        i32, LittleEndian => get_i32_le, try_get_i32_le, read_i32_le, try_read_i32_le;
+        u64, BigEndian => get_u64_be, try_get_u64_be, read_u64_be, try_read_u64_be;
+        u64, LittleEndian => get_u64_le, try_get_u64_le, read_u64_le, try_read_u64_le;
+        i64, BigEndian => get_i64_be, try_get_i64_be, read_i64_be, try_read_i64_be;
+        i64, LittleEndian => get_i64_le, try_get_i64_le, read_i64_le, try_read_i64_le;
+        u128, BigEndian => get_u128_be, try_get_u128_be, read_u128_be, try_read_u128_be;
+        u128, LittleEndian => get_u128_le, try_get_u128_le, read_u128_le, try_read_u128_le;
+        i128, BigEndian => get_i128_be, try_get_i128_be, read_i128_be, try_read_i128_be;
+        i128, LittleEndian => get_i128_le, try_get_i128_le, read_i128_le, try_read_i128_le;
+        f32, BigEndian => get_f32_be, try_get_f32_be, read_f32_be, try_read_f32_be;
+        f32, LittleEndian => get_f32_le, try_get_f32_le, read_f32_le, try_read_f32_le;
+        f64, BigEndian => get_f64_be, try_get_f64_be, read_f64_be, try_read_f64_be;
+        f64, LittleEndian => get_f64_le, try_get_f64_le, read_f64_le, try_read_f64_le;
+    }
+
+    /// The readable bytes as one `IoSlice` per component, for `Write::write_vectored`.
+    pub fn io_slices(&self) -> Vec<IoSlice<'_>> {
+        if !self.is_readable() {
+            return Vec::new();
+        }
+        let (first, off) = self.components.locate(self.read_index);
+        let mut slices = Vec::with_capacity(self.components.bufs.len() - first);
+        slices.push(IoSlice::new(&self.components.bufs[first].as_slice()[off..]));
+        slices.extend(self.components.bufs[first + 1..].iter().map(|c| IoSlice::new(c.as_slice())));
+        slices
+    }
+
+    /// Hands the readable bytes to `writer` in a single vectored write, advancing the reader index by what it took.
+    pub fn write_vectored_to<W: Write>(&mut self, writer: &mut W) -> io::Result<usize> {
+        let n = writer.write_vectored(&self.io_slices())?;
+        self.read_index += n;
+        Ok(n)
+    }
+}
+
+impl Default for CompositeByteBuf {
+    fn default() -> Self {
+        CompositeByteBuf::new()
+    }
+}
+
+impl Read for CompositeByteBuf {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        let n = cmp::min(buf.len(), self.readable_bytes());
+        self.read_bytes(&mut buf[..n]);
+        Ok(n)
+    }
+}
@@ -0,0 +1,130 @@
+use std::borrow::Cow;
+use std::cmp;
+use std::mem;
+
+use crate::bytebuf::{BigEndian, ByteOrder, ByteReult, FromBytes, LengthPrefix, LittleEndian};
+use crate::bytebuf::primitive::INLINE_SIZE;
+use crate::bytebuf::utf8::validate_utf8;
+use crate::bytebuf::varint::{self, MAX_VARINT_U64_BYTES};
+use crate::error::ByteBufError;
+
+// the bytes behind a reader, stored in one piece or in several
+pub(crate) trait ByteSource {
+    // `len` bytes from `index` on, when they are stored in one piece; the caller checks the range
+    fn contiguous(&self, index: usize, len: usize) -> Option<&[u8]>;
+
+    // fills `out` from `index` on; the caller checks the range
+    fn copy_at(&self, index: usize, out: &mut [u8]);
+
+    // `len` bytes from `index` on, borrowed when they are stored in one piece
+    fn bytes_at(&self, index: usize, len: usize) -> Cow<'_, [u8]> {
+        if let Some(bytes) = self.contiguous(index, len) {
+            return Cow::Borrowed(bytes);
+        }
+        let mut bytes = vec![0u8; len];
+        self.copy_at(index, &mut bytes);
+        Cow::Owned(bytes)
+    }
+}
+
+impl ByteSource for [u8] {
+    fn contiguous(&self, index: usize, len: usize) -> Option<&[u8]> {
+        self.get(index..index + len)
+    }
+
+    fn copy_at(&self, index: usize, out: &mut [u8]) {
+        out.copy_from_slice(&self[index..index + out.len()]);
+    }
+}
+
+// the readable range of a `ByteSource`; everything here peeks, the caller
+// advances its own reader index by what was used
+pub(crate) struct Cursor<'a, S: ?Sized> {
+    src: &'a S,
+    read_index: usize,
+    write_index: usize,
+}
+
+impl<'a, S: ByteSource + ?Sized> Cursor<'a, S> {
+    pub(crate) fn new(src: &'a S, read_index: usize, write_index: usize) -> Self {
+        Cursor { src, read_index, write_index }
+    }
+
+    pub(crate) fn check_readable(&self, len: usize) -> ByteReult<()> {
+        let readable = self.write_index.saturating_sub(self.read_index);
+        if len > readable {
+            return Err(ByteBufError::InsufficientBytes { needed: len, available: readable });
+        }
+        Ok(())
+    }
+
+    pub(crate) fn get_as<T: FromBytes, E: ByteOrder>(&self) -> ByteReult<T> {
+        self.check_readable(T::SIZE)?;
+        if let Some(bytes) = self.src.contiguous(self.read_index, T::SIZE) {
+            return Ok(T::from_bytes::<E>(bytes));
+        }
+        if T::SIZE <= INLINE_SIZE {
+            let mut bytes = [0u8; INLINE_SIZE];
+            self.src.copy_at(self.read_index, &mut bytes[..T::SIZE]);
+            Ok(T::from_bytes::<E>(&bytes[..T::SIZE]))
+        } else {
+            let mut bytes = vec![0u8; T::SIZE];
+            self.src.copy_at(self.read_index, &mut bytes);
+            Ok(T::from_bytes::<E>(&bytes))
+        }
+    }
+
+    // validates `len` bytes starting `skip` bytes past the reader index
+    pub(crate) fn get_str(&self, skip: usize, len: usize) -> ByteReult<Cow<'a, str>> {
+        self.check_readable(skip.saturating_add(len))?;
+        let start = self.read_index + skip;
+        let src: &'a S = self.src;
+        match src.bytes_at(start, len) {
+            Cow::Borrowed(bytes) => Ok(Cow::Borrowed(validate_utf8(bytes, start)?)),
+            Cow::Owned(bytes) => String::from_utf8(bytes)
+                .map(Cow::Owned)
+                .map_err(|e| ByteBufError::InvalidUtf8 { offset: start + e.utf8_error().valid_up_to() }),
+        }
+    }
+
+    // decodes the varint at the reader index, returning the value and its encoded length
+    pub(crate) fn get_varint(&self, max_bytes: usize, bits: u32) -> ByteReult<(u64, usize)> {
+        let n = cmp::min(max_bytes, self.write_index.saturating_sub(self.read_index));
+        let bytes = self.src.bytes_at(self.read_index, n);
+        varint::decode_varint(&bytes, self.read_index, max_bytes, bits)
+    }
+
+    // peeks the length at the reader index, returning it with the width of the prefix
+    pub(crate) fn get_len(&self, prefix: LengthPrefix) -> ByteReult<(usize, usize)> {
+        let (len, width) = match prefix {
+            LengthPrefix::Varint => self.get_varint(MAX_VARINT_U64_BYTES, 64)?,
+            LengthPrefix::U8 => (self.get_as::<u8, BigEndian>()? as u64, mem::size_of::<u8>()),
+            LengthPrefix::U16Be => (self.get_as::<u16, BigEndian>()? as u64, mem::size_of::<u16>()),
+            LengthPrefix::U16Le => (self.get_as::<u16, LittleEndian>()? as u64, mem::size_of::<u16>()),
+            LengthPrefix::U32Be => (self.get_as::<u32, BigEndian>()? as u64, mem::size_of::<u32>()),
+            LengthPrefix::U32Le => (self.get_as::<u32, LittleEndian>()? as u64, mem::size_of::<u32>()),
+            LengthPrefix::U64Be => (self.get_as::<u64, BigEndian>()?, mem::size_of::<u64>()),
+            LengthPrefix::U64Le => (self.get_as::<u64, LittleEndian>()?, mem::size_of::<u64>()),
+        };
+        if len > usize::MAX as u64 {
+            return Err(ByteBufError::LengthPrefixOverflow { len: usize::MAX, max: usize::MAX });
+        }
+        Ok((len as usize, width))
+    }
+
+    // like `get_len`, but also checks that the body after the prefix is readable
+    pub(crate) fn get_field_len(&self, prefix: LengthPrefix) -> ByteReult<(usize, usize)> {
+        let (len, width) = self.get_len(prefix)?;
+        self.check_readable(width.saturating_add(len))?;
+        Ok((len, width))
+    }
+}
+
+impl<'a> Cursor<'a, [u8]> {
+    // `get_str` for a single slice, where the result is always borrowed
+    pub(crate) fn get_str_ref(&self, skip: usize, len: usize) -> ByteReult<&'a str> {
+        self.check_readable(skip.saturating_add(len))?;
+        let start = self.read_index + skip;
+        validate_utf8(&self.src[start..start + len], start)
+    }
+}
@@ -0,0 +1,274 @@
+use std::io;
+
+use crate::bytebuf::{ByteBuf, ByteReult};
+use crate::bytebuf::primitive::{FromBytes, ToBytes};
+
+/// Byte order chosen at runtime rather than by picking a `_be`/`_le` method.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Endian {
+    #[default]
+    Big,
+    Little,
+}
+
+impl Endian {
+    pub const fn native() -> Endian {
+        if cfg!(target_endian = "big") { Endian::Big } else { Endian::Little }
+    }
+}
+
+/// Byte order picked at compile time, as in `buf.read_as::<u32, BigEndian>()`.
+pub trait ByteOrder {
+    const ENDIAN: Endian;
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct BigEndian;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct LittleEndian;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct NativeEndian;
+
+pub type BE = BigEndian;
+pub type LE = LittleEndian;
+pub type NE = NativeEndian;
+
+impl ByteOrder for BigEndian {
+    const ENDIAN: Endian = Endian::Big;
+}
+
+impl ByteOrder for LittleEndian {
+    const ENDIAN: Endian = Endian::Little;
+}
+
+impl ByteOrder for NativeEndian {
+    const ENDIAN: Endian = Endian::native();
+}
+
+// the fixed-width readers for one byte order, looked up through `$endian`
+macro_rules! read_accessors {
+    ($endian:ident; $($t:ty => $get:ident, $try_get:ident, $read:ident, $try_read:ident;)*) => {
+        $(
+            pub fn $get(&self) -> $t {
+                self.get_with::<$t>(self.$endian())
+            }
+
+            pub fn $try_get(&self) -> ByteReult<$t> {
+                self.try_get_with::<$t>(self.$endian())
+            }
+
+            pub fn $read(&mut self) -> $t {
+                self.read_with::<$t>(self.$endian())
+            }
+
+            pub fn $try_read(&mut self) -> ByteReult<$t> {
+                self.try_read_with::<$t>(self.$endian())
+            }
+        )*
+    };
+}
+
+// the fixed-width writers for one byte order, looked up through `$endian`
+macro_rules! write_accessors {
+    ($endian:ident; $($t:ty => $write:ident;)*) => {
+        $(
+            pub fn $write(&mut self, v: $t) -> io::Result<usize> {
+                self.write_with::<$t>(self.$endian(), v)
+            }
+        )*
+    };
+}
+
+// the fixed-width setters for one byte order, looked up through `$endian`
+macro_rules! set_accessors {
+    ($endian:ident; $($t:ty => $set:ident;)*) => {
+        $(
+            pub fn $set(&mut self, wid: usize, v: $t) -> ByteReult<$t> {
+                self.set_with::<$t>(self.$endian(), wid, v)?;
+                Ok(v)
+            }
+        )*
+    };
+}
+
+// the runtime endian readers, for any type with `get_as`, `try_get_as`, `read_as`
+// and `try_read_as` and the `endian`/`native_endian` lookups
+macro_rules! endian_readers {
+    () => {
+        pub fn get_with<T: FromBytes>(&self, endian: Endian) -> T {
+            match endian {
+                Endian::Big => self.get_as::<T, BigEndian>(),
+                Endian::Little => self.get_as::<T, LittleEndian>(),
+            }
+        }
+
+        pub fn try_get_with<T: FromBytes>(&self, endian: Endian) -> ByteReult<T> {
+            match endian {
+                Endian::Big => self.try_get_as::<T, BigEndian>(),
+                Endian::Little => self.try_get_as::<T, LittleEndian>(),
+            }
+        }
+
+        pub fn read_with<T: FromBytes>(&mut self, endian: Endian) -> T {
+            match endian {
+                Endian::Big => self.read_as::<T, BigEndian>(),
+                Endian::Little => self.read_as::<T, LittleEndian>(),
+            }
+        }
+
+        pub fn try_read_with<T: FromBytes>(&mut self, endian: Endian) -> ByteReult<T> {
+            match endian {
+                Endian::Big => self.try_read_as::<T, BigEndian>(),
+                Endian::Little => self.try_read_as::<T, LittleEndian>(),
+            }
+        }
+
+        // readers following `endian()`
+
+        $crate::bytebuf::endian::read_accessors! {
+            endian;
+            u16 => get_u16, try_get_u16, read_u16, try_read_u16;
+            i16 => get_i16, try_get_i16, read_i16, try_read_i16;
+            u32 => get_u32, try_get_u32, read_u32, try_read_u32;
+            i32 => get_i32, try_get_i32, read_i32, try_read_i32;
+            u64 => get_u64, try_get_u64, read_u64, try_read_u64;
+            i64 => get_i64, try_get_i64, read_i64, try_read_i64;
+            u128 => get_u128, try_get_u128, read_u128, try_read_u128;
+            i128 => get_i128, try_get_i128, read_i128, try_read_i128;
+            f32 => get_f32, try_get_f32, read_f32, try_read_f32;
+            f64 => get_f64, try_get_f64, read_f64, try_read_f64;
+        }
+
+        // native endian readers
+
+        $crate::bytebuf::endian::read_accessors! {
+            native_endian;
+            u16 => get_u16_ne, try_get_u16_ne, read_u16_ne, try_read_u16_ne;
+            i16 => get_i16_ne, try_get_i16_ne, read_i16_ne, try_read_i16_ne;
+            u32 => get_u32_ne, try_get_u32_ne, read_u32_ne, try_read_u32_ne;
+            i32 => get_i32_ne, try_get_i32_ne, read_i32_ne, try_read_i32_ne;
+            u64 => get_u64_ne, try_get_u64_ne, read_u64_ne, try_read_u64_ne;
+            i64 => get_i64_ne, try_get_i64_ne, read_i64_ne, try_read_i64_ne;
+            u128 => get_u128_ne, try_get_u128_ne, read_u128_ne, try_read_u128_ne;
+            i128 => get_i128_ne, try_get_i128_ne, read_i128_ne, try_read_i128_ne;
+            f32 => get_f32_ne, try_get_f32_ne, read_f32_ne, try_read_f32_ne;
+            f64 => get_f64_ne, try_get_f64_ne, read_f64_ne, try_read_f64_ne;
+        }
+    };
+}
+
+// the runtime endian writers, for any type with `write_with` and the
+// `endian`/`native_endian` lookups
+macro_rules! endian_writers {
+    () => {
+        // writers following `endian()`
+
+        $crate::bytebuf::endian::write_accessors! {
+            endian;
+            u16 => write_u16;
+            i16 => write_i16;
+            u32 => write_u32;
+            i32 => write_i32;
+            u64 => write_u64;
+            i64 => write_i64;
+            u128 => write_u128;
+            i128 => write_i128;
+            f32 => write_f32;
+            f64 => write_f64;
+        }
+
+        // native endian writers
+
+        $crate::bytebuf::endian::write_accessors! {
+            native_endian;
+            u16 => write_u16_ne;
+            i16 => write_i16_ne;
+            u32 => write_u32_ne;
+            i32 => write_i32_ne;
+            u64 => write_u64_ne;
+            i64 => write_i64_ne;
+            u128 => write_u128_ne;
+            i128 => write_i128_ne;
+            f32 => write_f32_ne;
+            f64 => write_f64_ne;
+        }
+    };
+}
+
+pub(crate) use read_accessors;
+pub(crate) use endian_readers;
+pub(crate) use write_accessors;
+pub(crate) use endian_writers;
+
+impl ByteBuf {
+    /// Byte order used by the accessors without a `_be`/`_le`/`_ne` suffix, big endian by default.
+    pub fn endian(&self) -> Endian {
+        self.endian
+    }
+
+    pub fn set_endian(&mut self, endian: Endian) {
+        self.endian = endian;
+    }
+
+    pub(crate) fn native_endian(&self) -> Endian {
+        Endian::native()
+    }
+
+
+    // runtime endian methods
+
+    endian_readers!();
+
+    pub fn write_with<T: ToBytes>(&mut self, endian: Endian, v: T) -> io::Result<usize> {
+        match endian {
+            Endian::Big => self.write_as::<T, BigEndian>(v),
+            Endian::Little => self.write_as::<T, LittleEndian>(v),
+        }
+    }
+
+    pub fn set_with<T: ToBytes>(&mut self, endian: Endian, wid: usize, v: T) -> ByteReult<()> {
+        match endian {
+            Endian::Big => self.set_at::<T, BigEndian>(wid, v),
+            Endian::Little => self.set_at::<T, LittleEndian>(wid, v),
+        }
+    }
+
+
+    endian_writers!();
+
+
+    // setters following `endian()`
+
+    set_accessors! {
+        endian;
+        u16 => set_u16;
+        i16 => set_i16;
+        u32 => set_u32;
+        i32 => set_i32;
+        u64 => set_u64;
+        i64 => set_i64;
+        u128 => set_u128;
+        i128 => set_i128;
+        f32 => set_f32;
+        f64 => set_f64;
+    }
+
+
+    // native endian setters
+
+    set_accessors! {
+        native_endian;
+        u16 => set_u16_ne;
+        i16 => set_i16_ne;
+        u32 => set_u32_ne;
+        i32 => set_i32_ne;
+        u64 => set_u64_ne;
+        i64 => set_i64_ne;
+        u128 => set_u128_ne;
+        i128 => set_i128_ne;
+        f32 => set_f32_ne;
+        f64 => set_f64_ne;
+    }
+}
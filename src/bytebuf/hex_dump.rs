@@ -0,0 +1,123 @@
+use std::cmp;
+use std::fmt::{self, Display, Formatter, LowerHex, UpperHex, Write};
+
+use crate::bytebuf::ByteBuf;
+
+const BYTES_PER_ROW: usize = 16;
+
+// where the hex digits of column `i` start on a row
+fn hex_column(i: usize) -> usize {
+    10 + 3 * i + if i >= BYTES_PER_ROW / 2 { 1 } else { 0 }
+}
+
+fn write_row(out: &mut String, offset: usize, bytes: &[u8]) {
+    let _ = write!(out, "{:08x}  ", offset);
+    for i in 0..BYTES_PER_ROW {
+        match bytes.get(i) {
+            Some(b) => {
+                let _ = write!(out, "{:02x} ", b);
+            }
+            None => out.push_str("   "),
+        }
+        if i == BYTES_PER_ROW / 2 - 1 {
+            out.push(' ');
+        }
+    }
+    out.push_str(" |");
+    out.extend(bytes.iter().map(|&b| if b.is_ascii_graphic() || b == b' ' { b as char } else { '.' }));
+    out.push_str("|\n");
+}
+
+impl ByteBuf {
+    /// The written bytes in `hexdump -C` layout, with the reader and writer
+    /// indices marked as `^r` and `^w` under the rows they fall on.
+    pub fn hex_dump(&self) -> String {
+        self.hex_dump_range(0, self.write_index)
+    }
+
+    /// Like `hex_dump`, for `len` bytes from `start`, clamped to the written bytes.
+    pub fn hex_dump_range(&self, start: usize, len: usize) -> String {
+        let end = start.saturating_add(len).min(self.write_index);
+        let start = start.min(end);
+        let mut out = String::new();
+        let mut prev: Option<&[u8]> = None;
+        let mut squeezed = false;
+        let mut row = start;
+        while row < end {
+            let bytes = &self.buf[row..cmp::min(row + BYTES_PER_ROW, end)];
+            let markers = self.index_markers(row, bytes.len(), end);
+            // like hexdump, repeated rows collapse into a single `*`
+            if markers.is_none() && prev == Some(bytes) && bytes.len() == BYTES_PER_ROW {
+                if !squeezed {
+                    out.push_str("*\n");
+                    squeezed = true;
+                }
+            } else {
+                squeezed = false;
+                write_row(&mut out, row, bytes);
+                if let Some(markers) = markers {
+                    out.push_str(&markers);
+                    out.push('\n');
+                }
+            }
+            prev = Some(bytes);
+            row += BYTES_PER_ROW;
+        }
+        let _ = writeln!(out, "{:08x}", end);
+        out
+    }
+
+    // the marker line for a row of `len` bytes at `row`, if an index falls on it;
+    // an index just past the last byte of the range is shown on the last row
+    fn index_markers(&self, row: usize, len: usize, end: usize) -> Option<String> {
+        let on_row = |index: usize| index >= row && (index < row + len || index == end && row + len == end);
+        let mut line = vec![b' '; hex_column(BYTES_PER_ROW) + 3];
+        let mut marked = false;
+        for (index, label) in [(self.read_index, b'r'), (self.write_index, b'w')] {
+            if on_row(index) {
+                let col = hex_column(index - row);
+                if line[col] == b'^' {
+                    line[col + 2] = label;
+                } else {
+                    line[col] = b'^';
+                    line[col + 1] = label;
+                }
+                marked = true;
+            }
+        }
+        if !marked {
+            return None;
+        }
+        Some(String::from_utf8(line).unwrap().trim_end().to_string())
+    }
+
+    /// Prints `hex_dump` to stdout.
+    pub fn print_bytes(&self) {
+        print!("{}", self.hex_dump())
+    }
+}
+
+impl Display for ByteBuf {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.hex_dump())
+    }
+}
+
+// the readable bytes as one run of hex digits, `0x` prefixed with `{:#x}`
+impl LowerHex for ByteBuf {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        if f.alternate() {
+            f.write_str("0x")?;
+        }
+        self.buf[self.read_index..self.write_index].iter().try_for_each(|b| write!(f, "{:02x}", b))
+    }
+}
+
+impl UpperHex for ByteBuf {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        if f.alternate() {
+            f.write_str("0x")?;
+        }
+        self.buf[self.read_index..self.write_index].iter().try_for_each(|b| write!(f, "{:02X}", b))
+    }
+}
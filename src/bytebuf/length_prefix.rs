@@ -0,0 +1,115 @@
+use crate::bytebuf::{ByteBuf, ByteReult};
+use crate::error::ByteBufError;
+
+/// Width and byte order of the length written in front of a string or blob.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum LengthPrefix {
+    Varint,
+    U8,
+    U16Be,
+    U16Le,
+    U32Be,
+    U32Le,
+    U64Be,
+    U64Le,
+}
+
+impl LengthPrefix {
+    pub fn max_len(&self) -> u64 {
+        match self {
+            LengthPrefix::Varint => u64::MAX,
+            LengthPrefix::U8 => u8::MAX as u64,
+            LengthPrefix::U16Be | LengthPrefix::U16Le => u16::MAX as u64,
+            LengthPrefix::U32Be | LengthPrefix::U32Le => u32::MAX as u64,
+            LengthPrefix::U64Be | LengthPrefix::U64Le => u64::MAX,
+        }
+    }
+}
+
+// the readers of length-prefixed fields, for any type with a `cursor()`, a
+// `read_index`, `read_bytes` and `read_string`
+macro_rules! len_readers {
+    () => {
+        /// Reads just a length prefix, e.g. the element count in front of a sequence.
+        pub fn read_len(&mut self, prefix: LengthPrefix) -> ByteReult<usize> {
+            let (len, width) = self.cursor().get_len(prefix)?;
+            self.read_index += width;
+            Ok(len)
+        }
+
+        // checks that a whole length-prefixed field is readable, consumes the prefix
+        // and returns the body length; on error nothing is consumed
+        fn try_read_len(&mut self, prefix: LengthPrefix) -> ByteReult<usize> {
+            let (len, width) = self.cursor().get_field_len(prefix)?;
+            self.read_index += width;
+            Ok(len)
+        }
+
+        pub fn read_bytes_with_len(&mut self, prefix: LengthPrefix) -> ByteReult<Vec<u8>> {
+            let len = self.try_read_len(prefix)?;
+            let mut bytes = vec![0u8; len];
+            self.read_bytes(&mut bytes);
+            Ok(bytes)
+        }
+
+        pub fn read_string_with_len(&mut self, prefix: LengthPrefix) -> ByteReult<String> {
+            let len = self.try_read_len(prefix)?;
+            Ok(self.read_string(len))
+        }
+
+        pub fn try_read_string_with_u8_be_len(&mut self) -> ByteReult<String> {
+            self.read_string_with_len(LengthPrefix::U8)
+        }
+
+        pub fn try_read_string_with_u16_be_len(&mut self) -> ByteReult<String> {
+            self.read_string_with_len(LengthPrefix::U16Be)
+        }
+
+        pub fn try_read_string_with_u16_le_len(&mut self) -> ByteReult<String> {
+            self.read_string_with_len(LengthPrefix::U16Le)
+        }
+
+        pub fn try_read_string_with_u32_be_len(&mut self) -> ByteReult<String> {
+            self.read_string_with_len(LengthPrefix::U32Be)
+        }
+
+        pub fn try_read_string_with_u32_le_len(&mut self) -> ByteReult<String> {
+            self.read_string_with_len(LengthPrefix::U32Le)
+        }
+    };
+}
+
+pub(crate) use len_readers;
+
+impl ByteBuf {
+    pub fn write_len(&mut self, prefix: LengthPrefix, len: usize) -> ByteReult<()> {
+        if len as u64 > prefix.max_len() {
+            return Err(ByteBufError::LengthPrefixOverflow { len, max: prefix.max_len() as usize });
+        }
+        match prefix {
+            LengthPrefix::Varint => self.write_varint_u64(len as u64)?,
+            LengthPrefix::U8 => self.write_u8_be(len as u8)?,
+            LengthPrefix::U16Be => self.write_u16_be(len as u16)?,
+            LengthPrefix::U16Le => self.write_u16_le(len as u16)?,
+            LengthPrefix::U32Be => self.write_u32_be(len as u32)?,
+            LengthPrefix::U32Le => self.write_u32_le(len as u32)?,
+            LengthPrefix::U64Be => self.write_u64_be(len as u64)?,
+            LengthPrefix::U64Le => self.write_u64_le(len as u64)?,
+        };
+        Ok(())
+    }
+
+
+
+
+    pub fn write_bytes_with_len(&mut self, prefix: LengthPrefix, v: &[u8]) -> ByteReult<usize> {
+        self.write_len(prefix, v.len())?;
+        Ok(self.write_bytes(v)?)
+    }
+
+    pub fn write_string_with_len(&mut self, prefix: LengthPrefix, v: &str) -> ByteReult<usize> {
+        self.write_bytes_with_len(prefix, v.as_bytes())
+    }
+
+    len_readers!();
+}
@@ -11,39 +11,123 @@ use std::result::Result;
 
 use crate::error::ByteBufError;
 
-pub mod slice_util;
+use self::cursor::Cursor;
+use self::storage::Storage;
 
-const CHUNK_SIZE: usize = 1024;
+pub mod slice_util;
+mod std_io;
+pub mod varint;
+pub mod length_prefix;
+pub mod endian;
+pub mod primitive;
+mod utf8;
+mod cursor;
+mod hex_dump;
+mod storage;
+mod slice;
+pub mod composite;
+pub mod pool;
+pub mod capacity;
+pub mod text_encoding;
+pub mod search;
+pub mod checksum;
+pub mod bits;
+#[cfg(feature = "bytes")]
+mod bytes_buf;
+#[cfg(feature = "tokio")]
+mod tokio_io;
+
+pub use endian::{BigEndian, ByteOrder, Endian, LittleEndian, NativeEndian, BE, LE, NE};
+pub use primitive::{FromBytes, ToBytes};
+pub use length_prefix::LengthPrefix;
+pub use composite::CompositeByteBuf;
+pub use pool::{ByteBufAllocator, ByteBufPool, PooledByteBuf};
+pub use capacity::GrowthStrategy;
+pub use text_encoding::Alphabet;
+pub use checksum::{Checksum, ChecksumKind, ChecksumWriter, Crc16};
+pub use bits::{BitOrder, BitReader, BitWriter};
+#[cfg(feature = "bytes")]
+pub use bytes_buf::BufAdapter;
+
+pub const CHUNK_SIZE: usize = 1024;
+
+// as much as a Vec can hold
+const DEFAULT_MAX_CAPACITY: usize = isize::MAX as usize;
 
 pub type ByteReult<T> = Result<T, ByteBufError>;
 
 pub struct ByteBuf {
-    buf: Vec<u8>,
+    buf: Storage,
     capacity: usize,
+    max_capacity: usize,
+    growth: GrowthStrategy,
     read_mark: isize,
     write_mark: isize,
     read_index: usize,
     write_index: usize,
+    discard_threshold: Option<usize>,
+    endian: Endian,
 }
 
+// the written bytes; a slice rather than the backing Vec, which may be shared
 impl Deref for ByteBuf {
-    type Target = Vec<u8>;
+    type Target = [u8];
 
-    fn deref(&self) -> &Vec<u8> {
-        return &self.buf;
+    fn deref(&self) -> &[u8] {
+        &self.buf
     }
 }
 
 impl DerefMut for ByteBuf {
-    fn deref_mut(&mut self) -> &mut Vec<u8> {
-        return &mut self.buf;
+    fn deref_mut(&mut self) -> &mut [u8] {
+        let len = self.buf.len();
+        self.buf.slice_mut(0..len, self.capacity)
+    }
+}
+
+
+impl Clone for ByteBuf {
+    fn clone(&self) -> ByteBuf {
+        let ByteBuf {
+            buf, capacity, max_capacity, growth, read_mark, write_mark, read_index, write_index, discard_threshold, endian
+        } = self;
+        ByteBuf {
+            buf: buf.clone(),
+            capacity: *capacity,
+            max_capacity: *max_capacity,
+            growth: *growth,
+            read_mark: -1,
+            write_mark: -1,
+            read_index: *read_index,
+            write_index: *write_index,
+            discard_threshold: *discard_threshold,
+            endian: *endian,
+        }
+    }
+}
+
+
+impl From<Vec<u8>> for ByteBuf {
+    fn from(buf: Vec<u8>) -> Self {
+        ByteBuf {
+            capacity: buf.len(),
+            max_capacity: DEFAULT_MAX_CAPACITY,
+            growth: GrowthStrategy::default(),
+            read_mark: -1,
+            write_mark: -1,
+            read_index: 0,
+            write_index: buf.len(),
+            discard_threshold: None,
+            endian: Endian::Big,
+            buf: Storage::new(buf),
+        }
     }
 }
 
 
 impl Debug for ByteBuf {
     fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
-        write!(f, "{:?}", self.buf.as_slice())
+        write!(f, "{:?}", &self.buf[..])
     }
 }
 
@@ -54,272 +138,198 @@ impl ByteBuf {
             capacity = CHUNK_SIZE
         }
         ByteBuf {
-            buf: Vec::with_capacity(capacity),
+            buf: Storage::new(Vec::with_capacity(capacity)),
             capacity,
+            max_capacity: DEFAULT_MAX_CAPACITY,
+            growth: GrowthStrategy::default(),
             read_mark: -1,
             write_mark: -1,
             read_index: 0,
             write_index: 0,
-
+            discard_threshold: None,
+            endian: Endian::Big,
         }
     }
 
     pub fn new_from(src: &[u8]) -> Self {
         ByteBuf {
-            buf: Vec::from(src),
+            buf: Storage::new(Vec::from(src)),
             capacity: src.len(),
+            max_capacity: DEFAULT_MAX_CAPACITY,
+            growth: GrowthStrategy::default(),
             read_mark: -1,
             write_mark: -1,
             read_index: 0,
-            write_index: if src.len() == 0 { 0 } else { src.len() },
+            write_index: src.len(),
+            discard_threshold: None,
+            endian: Endian::Big,
         }
     }
 
+    /// How many bytes fit before the next write has to grow the buffer.
     pub fn capacity(&self) -> usize {
         self.capacity
     }
 
-    pub fn clone(&self) -> ByteBuf {
-        let ByteBuf {
-            buf, capacity, read_mark, write_mark, read_index, write_index
-        } = self;
+    /// Copies the written bytes into a buffer of its own; `clone` shares them instead.
+    pub fn deep_clone(&self) -> ByteBuf {
+        let mut bytes = Vec::with_capacity(self.capacity);
+        bytes.extend_from_slice(self.available_bytes());
         ByteBuf {
-            buf: buf.clone(),
-            capacity: *capacity,
-            read_mark: -1,
-            write_mark: -1,
-            read_index: *read_index,
-            write_index: *write_index,
-        }
-    }
-
-
-    pub fn deep_clone(&mut self) -> ByteBuf {
-        let mut buf = &mut ByteBuf {
-            buf: vec![0u8; self.available_bytes().len() as usize],
+            buf: Storage::new(bytes),
             capacity: self.capacity,
+            max_capacity: self.max_capacity,
+            growth: self.growth,
             read_mark: -1,
             write_mark: -1,
             read_index: 0,
             write_index: self.write_index,
-        };
-        let mut bytes = self.buf.as_mut_slice();
-        buf.copy_from_slice(bytes, 0);
-        buf.clone()
+            discard_threshold: self.discard_threshold,
+            endian: self.endian,
+        }
     }
 
-
-    fn copy_from_slice(&mut self, src: &mut [u8], dest_start: usize) {
-        unsafe {
-            let s_ptr = src.as_mut_ptr();
-            let src_ptr = s_ptr.add(0);
-            let d_ptr = self.buf.as_mut_ptr();
-            let dest_ptr = d_ptr.add(dest_start);
-            ptr::copy(src_ptr, dest_ptr, src.len());
-        }
+    fn copy_from_slice(&mut self, src: &[u8], dest_start: usize) {
+        self.buf.slice_mut(dest_start..dest_start + src.len(), self.capacity).copy_from_slice(src);
     }
 
 
     // set methods
 
-    pub fn set_u8_be(&mut self, wid: usize, v: u8) -> ByteReult<u8> {
-        if (mem::size_of::<u8>() + wid) > self.capacity {
-            return Err(ByteBufError::new("wid out of capacity".to_string()));
+    // set_* only overwrite bytes below the writer index; reserve space with set_writer_index first
+    fn check_index(&self, index: usize, len: usize) -> ByteReult<()> {
+        if index.saturating_add(len) > self.write_index {
+            return Err(ByteBufError::IndexOutOfBounds { index, len, bound: self.write_index });
         }
-        self.copy_from_slice(&mut v.to_be_bytes(), wid);
+        Ok(())
+    }
+
+    pub fn set_u8_be(&mut self, wid: usize, v: u8) -> ByteReult<u8> {
+        self.set_at::<u8, BigEndian>(wid, v)?;
         Ok(v)
     }
 
     pub fn set_u8_le(&mut self, wid: usize, v: u8) -> ByteReult<u8> {
-        if (mem::size_of::<u8>() + wid) > self.capacity {
-            return Err(ByteBufError::new("wid out of capacity".to_string()));
-        }
-        self.copy_from_slice(&mut v.to_le_bytes(), wid);
+        self.set_at::<u8, LittleEndian>(wid, v)?;
         Ok(v)
     }
 
     pub fn set_i8_be(&mut self, wid: usize, v: i8) -> ByteReult<i8> {
-        if (mem::size_of::<i8>() + wid) > self.capacity {
-            return Err(ByteBufError::new("wid out of capacity".to_string()));
-        }
-        self.copy_from_slice(&mut v.to_be_bytes(), wid);
+        self.set_at::<i8, BigEndian>(wid, v)?;
         Ok(v)
     }
 
     pub fn set_i8_le(&mut self, wid: usize, v: i8) -> ByteReult<i8> {
-        if (mem::size_of::<i8>() + wid) > self.capacity {
-            return Err(ByteBufError::new("wid out of capacity".to_string()));
-        }
-        self.copy_from_slice(&mut v.to_le_bytes(), wid);
+        self.set_at::<i8, LittleEndian>(wid, v)?;
         Ok(v)
     }
 
 
     pub fn set_u16_be(&mut self, wid: usize, v: u16) -> ByteReult<u16> {
-        if (mem::size_of::<u16>() + wid) > self.capacity {
-            return Err(ByteBufError::new("wid out of capacity".to_string()));
-        }
-        self.copy_from_slice(&mut v.to_be_bytes(), wid);
+        self.set_at::<u16, BigEndian>(wid, v)?;
         Ok(v)
     }
 
     pub fn set_u16_le(&mut self, wid: usize, v: u16) -> ByteReult<u16> {
-        if (mem::size_of::<u16>() + wid) > self.capacity {
-            return Err(ByteBufError::new("wid out of capacity".to_string()));
-        }
-        self.copy_from_slice(&mut v.to_le_bytes(), wid);
+        self.set_at::<u16, LittleEndian>(wid, v)?;
         Ok(v)
     }
 
 
     pub fn set_i16_be(&mut self, wid: usize, v: i16) -> ByteReult<i16> {
-        if (mem::size_of::<i16>() + wid) > self.capacity {
-            return Err(ByteBufError::new("wid out of capacity".to_string()));
-        }
-        self.copy_from_slice(&mut v.to_be_bytes(), wid);
+        self.set_at::<i16, BigEndian>(wid, v)?;
         Ok(v)
     }
 
 
     pub fn set_i16_le(&mut self, wid: usize, v: i16) -> ByteReult<i16> {
-        if (mem::size_of::<i16>() + wid) > self.capacity {
-            return Err(ByteBufError::new("wid out of capacity".to_string()));
-        }
-        self.copy_from_slice(&mut v.to_le_bytes(), wid);
+        self.set_at::<i16, LittleEndian>(wid, v)?;
         Ok(v)
     }
 
 
     pub fn set_u32_be(&mut self, wid: usize, v: u32) -> ByteReult<u32> {
-        if (mem::size_of::<u32>() + wid) > self.capacity {
-            return Err(ByteBufError::new("wid out of capacity".to_string()));
-        }
-        self.copy_from_slice(&mut v.to_be_bytes(), wid);
+        self.set_at::<u32, BigEndian>(wid, v)?;
         Ok(v)
     }
 
     pub fn set_u32_le(&mut self, wid: usize, v: u32) -> ByteReult<u32> {
-        if (mem::size_of::<u32>() + wid) > self.capacity {
-            return Err(ByteBufError::new("wid out of capacity".to_string()));
-        }
-        self.copy_from_slice(&mut v.to_le_bytes(), wid);
+        self.set_at::<u32, LittleEndian>(wid, v)?;
         Ok(v)
     }
 
 
     pub fn set_i32_be(&mut self, wid: usize, v: i32) -> ByteReult<i32> {
-        if (mem::size_of::<i32>() + wid) > self.capacity {
-            return Err(ByteBufError::new("wid out of capacity".to_string()));
-        }
-        self.copy_from_slice(&mut v.to_be_bytes(), wid);
+        self.set_at::<i32, BigEndian>(wid, v)?;
         Ok(v)
     }
 
     pub fn set_i32_le(&mut self, wid: usize, v: i32) -> ByteReult<i32> {
-        if (mem::size_of::<i32>() + wid) > self.capacity {
-            return Err(ByteBufError::new("wid out of capacity".to_string()));
-        }
-        self.copy_from_slice(&mut v.to_le_bytes(), wid);
+        self.set_at::<i32, LittleEndian>(wid, v)?;
         Ok(v)
     }
 
 
     pub fn set_u64_be(&mut self, wid: usize, v: u64) -> ByteReult<u64> {
-        if (mem::size_of::<u64>() + wid) > self.capacity {
-            return Err(ByteBufError::new("wid out of capacity".to_string()));
-        }
-        self.copy_from_slice(&mut v.to_be_bytes(), wid);
+        self.set_at::<u64, BigEndian>(wid, v)?;
         Ok(v)
     }
 
     pub fn set_u64_le(&mut self, wid: usize, v: u64) -> ByteReult<u64> {
-        if (mem::size_of::<u64>() + wid) > self.capacity {
-            return Err(ByteBufError::new("wid out of capacity".to_string()));
-        }
-        self.copy_from_slice(&mut v.to_le_bytes(), wid);
+        self.set_at::<u64, LittleEndian>(wid, v)?;
         Ok(v)
     }
 
 
     pub fn set_i64_be(&mut self, wid: usize, v: i64) -> ByteReult<i64> {
-        if (mem::size_of::<i64>() + wid) > self.capacity {
-            return Err(ByteBufError::new("wid out of capacity".to_string()));
-        }
-        self.copy_from_slice(&mut v.to_be_bytes(), wid);
+        self.set_at::<i64, BigEndian>(wid, v)?;
         Ok(v)
     }
 
 
     pub fn set_i64_le(&mut self, wid: usize, v: i64) -> ByteReult<i64> {
-        if (mem::size_of::<i64>() + wid) > self.capacity {
-            return Err(ByteBufError::new("wid out of capacity".to_string()));
-        }
-        self.copy_from_slice(&mut v.to_le_bytes(), wid);
+        self.set_at::<i64, LittleEndian>(wid, v)?;
         Ok(v)
     }
 
 
     pub fn set_u128_be(&mut self, wid: usize, v: u128) -> ByteReult<u128> {
-        if (mem::size_of::<u128>() + wid) > self.capacity {
-            return Err(ByteBufError::new("wid out of capacity".to_string()));
-        }
-        self.copy_from_slice(&mut v.to_be_bytes(), wid);
+        self.set_at::<u128, BigEndian>(wid, v)?;
         Ok(v)
     }
 
     pub fn set_u128_le(&mut self, wid: usize, v: u128) -> ByteReult<u128> {
-        if (mem::size_of::<u128>() + wid) > self.capacity {
-            return Err(ByteBufError::new("wid out of capacity".to_string()));
-        }
-        self.copy_from_slice(&mut v.to_le_bytes(), wid);
+        self.set_at::<u128, LittleEndian>(wid, v)?;
         Ok(v)
     }
 
     pub fn set_i128_be(&mut self, wid: usize, v: i128) -> ByteReult<i128> {
-        if (mem::size_of::<i128>() + wid) > self.capacity {
-            return Err(ByteBufError::new("wid out of capacity".to_string()));
-        }
-        self.copy_from_slice(&mut v.to_be_bytes(), wid);
+        self.set_at::<i128, BigEndian>(wid, v)?;
         Ok(v)
     }
 
     pub fn set_i128_le(&mut self, wid: usize, v: i128) -> ByteReult<i128> {
-        if (mem::size_of::<i128>() + wid) > self.capacity {
-            return Err(ByteBufError::new("wid out of capacity".to_string()));
-        }
-        self.copy_from_slice(&mut v.to_le_bytes(), wid);
+        self.set_at::<i128, LittleEndian>(wid, v)?;
         Ok(v)
     }
 
     pub fn set_f32_be(&mut self, wid: usize, v: f32) -> ByteReult<f32> {
-        if (mem::size_of::<f32>() + wid) > self.capacity {
-            return Err(ByteBufError::new("wid out of capacity".to_string()));
-        }
-        self.copy_from_slice(&mut v.to_be_bytes(), wid);
+        self.set_at::<f32, BigEndian>(wid, v)?;
         Ok(v)
     }
 
     pub fn set_f32_le(&mut self, wid: usize, v: f32) -> ByteReult<f32> {
-        if (mem::size_of::<f32>() + wid) > self.capacity {
-            return Err(ByteBufError::new("wid out of capacity".to_string()));
-        }
-        self.copy_from_slice(&mut v.to_le_bytes(), wid);
+        self.set_at::<f32, LittleEndian>(wid, v)?;
         Ok(v)
     }
 
     pub fn set_f64_be(&mut self, wid: usize, v: f64) -> ByteReult<f64> {
-        if (mem::size_of::<f64>() + wid) > self.capacity {
-            return Err(ByteBufError::new("wid out of capacity".to_string()));
-        }
-        self.copy_from_slice(&mut v.to_be_bytes(), wid);
+        self.set_at::<f64, BigEndian>(wid, v)?;
         Ok(v)
     }
 
     pub fn set_f64_le(&mut self, wid: usize, v: f64) -> ByteReult<f64> {
-        if (mem::size_of::<f64>() + wid) > self.capacity {
-            return Err(ByteBufError::new("wid out of capacity".to_string()));
-        }
-        self.copy_from_slice(&mut v.to_le_bytes(), wid);
+        self.set_at::<f64, LittleEndian>(wid, v)?;
         Ok(v)
     }
 
@@ -333,186 +343,99 @@ impl ByteBuf {
     }
 
     pub fn get_bool(&self) -> bool {
-        let v = self.buf.as_slice()[self.read_index..self.read_index + mem::size_of::<bool>()].first().unwrap();
-        if *v == 0x01u8 {
-            true
-        } else {
-            false
-        }
+        self.get_as::<bool, BigEndian>()
     }
 
 
     pub fn get_u8(&self) -> u8 {
-        let v = *self.buf.as_slice()[self.read_index..self.read_index + mem::size_of::<u8>()].first().unwrap();
-        v
+        self.get_as::<u8, BigEndian>()
     }
 
     pub fn get_i8(&self) -> i8 {
-        let v = self.buf.as_slice()[self.read_index..self.read_index + mem::size_of::<i8>()].first().unwrap();
-        *v as i8
-    }
-
-    fn get_u16_bytes(&self) -> [u8; mem::size_of::<u16>()] {
-        let len = mem::size_of::<u16>();
-        let byte = &self.buf.as_slice()[self.read_index..self.read_index + len];
-        let mut x = [0u8; mem::size_of::<u16>()];
-        x.copy_from_slice(byte);
-        x
+        self.get_as::<i8, BigEndian>()
     }
 
     pub fn get_u16_be(&self) -> u16 {
-        u16::from_be_bytes(self.get_u16_bytes())
+        self.get_as::<u16, BigEndian>()
     }
     pub fn get_u16_le(&self) -> u16 {
-        u16::from_le_bytes(self.get_u16_bytes())
+        self.get_as::<u16, LittleEndian>()
     }
 
 
-    fn get_i16_bytes(&self) -> [u8; mem::size_of::<i16>()] {
-        let len = mem::size_of::<i16>();
-        let byte = &self.buf.as_slice()[self.read_index..self.read_index + len];
-        let mut x = [0u8; mem::size_of::<i16>()];
-        x.copy_from_slice(byte);
-        x
-    }
-
     pub fn get_i16_be(&self) -> i16 {
-        i16::from_be_bytes(self.get_i16_bytes())
+        self.get_as::<i16, BigEndian>()
     }
 
     pub fn get_i16_le(&self) -> i16 {
-        i16::from_le_bytes(self.get_i16_bytes())
+        self.get_as::<i16, LittleEndian>()
     }
 
-
-    fn get_u32_bytes(&self) -> [u8; mem::size_of::<u32>()] {
-        let len = mem::size_of::<u32>();
-        let byte = &self.buf.as_slice()[self.read_index..self.read_index + len];
-        let mut x = [0u8; mem::size_of::<u32>()];
-        x.copy_from_slice(byte);
-        x
-    }
     pub fn get_u32_be(&self) -> u32 {
-        u32::from_be_bytes(self.get_u32_bytes())
+        self.get_as::<u32, BigEndian>()
     }
 
     pub fn get_u32_le(&self) -> u32 {
-        u32::from_le_bytes(self.get_u32_bytes())
+        self.get_as::<u32, LittleEndian>()
     }
 
 
-    fn get_i32_bytes(&self) -> [u8; mem::size_of::<i32>()] {
-        let len = mem::size_of::<i32>();
-        let byte = &self.buf.as_slice()[self.read_index..self.read_index + len];
-        let mut x = [0u8; mem::size_of::<i32>()];
-        x.copy_from_slice(byte);
-        x
-    }
-
     pub fn get_i32_be(&self) -> i32 {
-        i32::from_be_bytes(self.get_i32_bytes())
+        self.get_as::<i32, BigEndian>()
     }
 
     pub fn get_i32_le(&self) -> i32 {
-        i32::from_le_bytes(self.get_i32_bytes())
-    }
-
-    fn get_u64_bytes(&self) -> [u8; mem::size_of::<u64>()] {
-        let len = mem::size_of::<u64>();
-        let byte = &self.buf.as_slice()[self.read_index..self.read_index + len];
-        let mut x = [0u8; mem::size_of::<u64>()];
-        x.copy_from_slice(byte);
-        x
+        self.get_as::<i32, LittleEndian>()
     }
 
     pub fn get_u64_be(&self) -> u64 {
-        u64::from_be_bytes(self.get_u64_bytes())
+        self.get_as::<u64, BigEndian>()
     }
 
     pub fn get_u64_le(&self) -> u64 {
-        u64::from_le_bytes(self.get_u64_bytes())
+        self.get_as::<u64, LittleEndian>()
     }
 
 
-    fn get_i64_bytes(&self) -> [u8; mem::size_of::<i64>()] {
-        let len = mem::size_of::<i64>();
-        let byte = &self.buf.as_slice()[self.read_index..self.read_index + len];
-        let mut x = [0u8; mem::size_of::<i64>()];
-        x.copy_from_slice(byte);
-        x
-    }
-
     pub fn get_i64_be(&self) -> i64 {
-        i64::from_be_bytes(self.get_i64_bytes())
+        self.get_as::<i64, BigEndian>()
     }
 
     pub fn get_i64_le(&self) -> i64 {
-        i64::from_le_bytes(self.get_i64_bytes())
+        self.get_as::<i64, LittleEndian>()
     }
 
 
-    fn get_u128_bytes(&self) -> [u8; mem::size_of::<u128>()] {
-        let len = mem::size_of::<u128>();
-        let byte = &self.buf.as_slice()[self.read_index..self.read_index + len];
-        let mut x = [0u8; mem::size_of::<u128>()];
-        x.copy_from_slice(byte);
-        x
-    }
-
     pub fn get_u128_be(&self) -> u128 {
-        u128::from_be_bytes(self.get_u128_bytes())
+        self.get_as::<u128, BigEndian>()
     }
 
     pub fn get_u128_le(&self) -> u128 {
-        u128::from_le_bytes(self.get_u128_bytes())
-    }
-
-    fn get_i128_bytes(&self) -> [u8; mem::size_of::<i128>()] {
-        let len = mem::size_of::<i128>();
-        let byte = &self.buf.as_slice()[self.read_index..self.read_index + len];
-        let mut x = [0u8; mem::size_of::<i128>()];
-        x.copy_from_slice(byte);
-        x
+        self.get_as::<u128, LittleEndian>()
     }
 
     pub fn get_i128_be(&self) -> i128 {
-        i128::from_be_bytes(self.get_i128_bytes())
+        self.get_as::<i128, BigEndian>()
     }
 
     pub fn get_i128_le(&self) -> i128 {
-        i128::from_le_bytes(self.get_i128_bytes())
-    }
-
-    fn get_f32_bytes(&self) -> [u8; mem::size_of::<f32>()] {
-        let len = mem::size_of::<f32>();
-        let byte = &self.buf.as_slice()[self.read_index..self.read_index + len];
-        let mut x = [0u8; mem::size_of::<f32>()];
-        x.copy_from_slice(byte);
-        x
+        self.get_as::<i128, LittleEndian>()
     }
 
     pub fn get_f32_be(&self) -> f32 {
-        f32::from_be_bytes(self.get_f32_bytes())
+        self.get_as::<f32, BigEndian>()
     }
 
     pub fn get_f32_le(&self) -> f32 {
-        f32::from_le_bytes(self.get_f32_bytes())
-    }
-
-    fn get_f64_bytes(&self) -> [u8; mem::size_of::<f64>()] {
-        let len = mem::size_of::<f64>();
-        let byte = &self.buf.as_slice()[self.read_index..self.read_index + len];
-        let mut x = [0u8; mem::size_of::<f64>()];
-        x.copy_from_slice(byte);
-        x
+        self.get_as::<f32, LittleEndian>()
     }
 
     pub fn get_f64_be(&self) -> f64 {
-        f64::from_be_bytes(self.get_f64_bytes())
+        self.get_as::<f64, BigEndian>()
     }
 
     pub fn get_f64_le(&self) -> f64 {
-        f64::from_be_bytes(self.get_f64_bytes())
+        self.get_as::<f64, LittleEndian>()
     }
 
 
@@ -520,7 +443,7 @@ impl ByteBuf {
         if len == 0 {
             return String::from("");
         }
-        let x = &self.buf.as_slice()[self.read_index..self.read_index + len];
+        let x = &self.buf[self.read_index..self.read_index + len];
         self.read_index += len;
         String::from_utf8_lossy(x).to_string()
     }
@@ -559,469 +482,545 @@ impl ByteBuf {
     }
 
     pub fn read_bool(&mut self) -> bool {
-        let r = self.get_bool();
-        self.read_index += 1;
-        r
+        self.read_as::<bool, BigEndian>()
     }
 
     pub fn read_u8(&mut self) -> u8 {
-        let r = self.get_u8();
-        self.read_index += 1;
-        r
+        self.read_as::<u8, BigEndian>()
     }
 
     pub fn read_i8(&mut self) -> i8 {
-        let r = self.get_i8();
-        self.read_index += 1;
-        r
+        self.read_as::<i8, BigEndian>()
     }
 
     pub fn read_u16_be(&mut self) -> u16 {
-        let len = mem::size_of::<u16>();
-        let r = self.get_u16_be();
-        self.read_index += len;
-        r
+        self.read_as::<u16, BigEndian>()
     }
 
     pub fn read_u16_le(&mut self) -> u16 {
-        let len = mem::size_of::<u16>();
-        let r = self.get_u16_le();
-        self.read_index += len;
-        r
+        self.read_as::<u16, LittleEndian>()
     }
 
     pub fn read_i16_be(&mut self) -> i16 {
-        let len = mem::size_of::<i16>();
-        let r = self.get_i16_be();
-        self.read_index += len;
-        r
+        self.read_as::<i16, BigEndian>()
     }
 
 
     pub fn read_i16_le(&mut self) -> i16 {
-        let len = mem::size_of::<i16>();
-        let r = self.get_i16_le();
-        self.read_index += len;
-        r
+        self.read_as::<i16, LittleEndian>()
     }
 
 
     pub fn read_u32_be(&mut self) -> u32 {
-        let len = mem::size_of::<u32>();
-        let r = self.get_u32_be();
-        self.read_index += len;
-        return r;
+        self.read_as::<u32, BigEndian>()
     }
 
     pub fn read_u32_le(&mut self) -> u32 {
-        let len = mem::size_of::<u32>();
-        let r = self.get_u32_le();
-        self.read_index += len;
-        r
+        self.read_as::<u32, LittleEndian>()
     }
 
 
     pub fn read_i32_be(&mut self) -> i32 {
-        let len = mem::size_of::<i32>();
-        let r = self.get_i32_be();
-        self.read_index += len;
-        r
+        self.read_as::<i32, BigEndian>()
     }
 
     pub fn read_i32_le(&mut self) -> i32 {
-        let len = mem::size_of::<i32>();
-        let r = self.get_i32_le();
-        self.read_index += len;
-        r
+        self.read_as::<i32, LittleEndian>()
     }
 
     pub fn read_u64_be(&mut self) -> u64 {
-        let len = mem::size_of::<u64>();
-        let r = self.get_u64_be();
-        self.read_index += len;
-        r
+        self.read_as::<u64, BigEndian>()
     }
 
 
     pub fn read_u64_le(&mut self) -> u64 {
-        let len = mem::size_of::<u64>();
-        let r = self.get_u64_le();
-        self.read_index += len;
-        r
+        self.read_as::<u64, LittleEndian>()
     }
 
 
     pub fn read_i64_be(&mut self) -> i64 {
-        let len = mem::size_of::<i64>();
-        let r = self.get_i64_be();
-        self.read_index += len;
-        r
+        self.read_as::<i64, BigEndian>()
     }
 
     pub fn read_i64_le(&mut self) -> i64 {
-        let len = mem::size_of::<i64>();
-        let r = self.get_i64_le();
-        self.read_index += len;
-        r
+        self.read_as::<i64, LittleEndian>()
     }
 
     pub fn read_u128_be(&mut self) -> u128 {
-        let len = mem::size_of::<u128>();
-        let r = self.get_u128_be();
-        self.read_index += len;
-        r
+        self.read_as::<u128, BigEndian>()
     }
 
     pub fn read_u128_le(&mut self) -> u128 {
-        let len = mem::size_of::<u128>();
-        let r = self.get_u128_le();
-        self.read_index += len;
-        r
+        self.read_as::<u128, LittleEndian>()
     }
 
     pub fn read_i128_be(&mut self) -> i128 {
-        let len = mem::size_of::<i128>();
-        let r = self.get_i128_be();
-        self.read_index += len;
-        r
+        self.read_as::<i128, BigEndian>()
     }
 
     pub fn read_i128_le(&mut self) -> i128 {
-        let len = mem::size_of::<i128>();
-        let r = self.get_i128_le();
-        self.read_index += len;
-        r
+        self.read_as::<i128, LittleEndian>()
     }
 
 
     pub fn read_f32_be(&mut self) -> f32 {
-        let len = mem::size_of::<f32>();
-        let r = self.get_f32_be();
-        self.read_index += len;
-        r
+        self.read_as::<f32, BigEndian>()
     }
 
     pub fn read_f32_le(&mut self) -> f32 {
-        let len = mem::size_of::<f32>();
-        let r = self.get_f32_le();
-        self.read_index += len;
-        r
+        self.read_as::<f32, LittleEndian>()
     }
 
     pub fn read_f64_be(&mut self) -> f64 {
-        let len = mem::size_of::<f64>();
-        let r = self.get_f64_be();
-        self.read_index += len;
-        r
+        self.read_as::<f64, BigEndian>()
     }
 
     pub fn read_f64_le(&mut self) -> f64 {
-        let len = mem::size_of::<f64>();
-        let r = self.get_f64_le();
-        self.read_index += len;
-        r
+        self.read_as::<f64, LittleEndian>()
+    }
+
+    // try get methods
+
+    // the readable bytes, for the readers shared with `CompositeByteBuf`
+    pub(crate) fn cursor(&self) -> Cursor<'_, [u8]> {
+        Cursor::new(&self.buf[..], self.read_index, self.write_index)
+    }
+
+    fn check_readable(&self, len: usize) -> ByteReult<()> {
+        self.cursor().check_readable(len)
     }
 
+    pub fn try_get_bytes(&self, bytes: &mut [u8]) -> ByteReult<usize> {
+        self.check_readable(bytes.len())?;
+        Ok(self.get_bytes(bytes))
+    }
+
+    pub fn try_get_bool(&self) -> ByteReult<bool> {
+        self.try_get_as::<bool, BigEndian>()
+    }
+
+    pub fn try_get_u8(&self) -> ByteReult<u8> {
+        self.try_get_as::<u8, BigEndian>()
+    }
+
+    pub fn try_get_i8(&self) -> ByteReult<i8> {
+        self.try_get_as::<i8, BigEndian>()
+    }
+
+    pub fn try_get_u16_be(&self) -> ByteReult<u16> {
+        self.try_get_as::<u16, BigEndian>()
+    }
+
+    pub fn try_get_u16_le(&self) -> ByteReult<u16> {
+        self.try_get_as::<u16, LittleEndian>()
+    }
+
+    pub fn try_get_i16_be(&self) -> ByteReult<i16> {
+        self.try_get_as::<i16, BigEndian>()
+    }
+
+    pub fn try_get_i16_le(&self) -> ByteReult<i16> {
+        self.try_get_as::<i16, LittleEndian>()
+    }
+
+    pub fn try_get_u32_be(&self) -> ByteReult<u32> {
+        self.try_get_as::<u32, BigEndian>()
+    }
+
+    pub fn try_get_u32_le(&self) -> ByteReult<u32> {
+        self.try_get_as::<u32, LittleEndian>()
+    }
+
+    pub fn try_get_i32_be(&self) -> ByteReult<i32> {
+        self.try_get_as::<i32, BigEndian>()
+    }
+
+    pub fn try_get_i32_le(&self) -> ByteReult<i32> {
+        self.try_get_as::<i32, LittleEndian>()
+    }
+
+    pub fn try_get_u64_be(&self) -> ByteReult<u64> {
+        self.try_get_as::<u64, BigEndian>()
+    }
+
+    pub fn try_get_u64_le(&self) -> ByteReult<u64> {
+        self.try_get_as::<u64, LittleEndian>()
+    }
+
+    pub fn try_get_i64_be(&self) -> ByteReult<i64> {
+        self.try_get_as::<i64, BigEndian>()
+    }
+
+    pub fn try_get_i64_le(&self) -> ByteReult<i64> {
+        self.try_get_as::<i64, LittleEndian>()
+    }
+
+    pub fn try_get_u128_be(&self) -> ByteReult<u128> {
+        self.try_get_as::<u128, BigEndian>()
+    }
+
+    pub fn try_get_u128_le(&self) -> ByteReult<u128> {
+        self.try_get_as::<u128, LittleEndian>()
+    }
+
+    pub fn try_get_i128_be(&self) -> ByteReult<i128> {
+        self.try_get_as::<i128, BigEndian>()
+    }
+
+    pub fn try_get_i128_le(&self) -> ByteReult<i128> {
+        self.try_get_as::<i128, LittleEndian>()
+    }
+
+    pub fn try_get_f32_be(&self) -> ByteReult<f32> {
+        self.try_get_as::<f32, BigEndian>()
+    }
+
+    pub fn try_get_f32_le(&self) -> ByteReult<f32> {
+        self.try_get_as::<f32, LittleEndian>()
+    }
+
+    pub fn try_get_f64_be(&self) -> ByteReult<f64> {
+        self.try_get_as::<f64, BigEndian>()
+    }
+
+    pub fn try_get_f64_le(&self) -> ByteReult<f64> {
+        self.try_get_as::<f64, LittleEndian>()
+    }
+
+    // try get end
+
+
+    // try read methods
+
+    pub fn try_read_string(&mut self, len: usize) -> ByteReult<String> {
+        self.check_readable(len)?;
+        Ok(self.read_string(len))
+    }
+
+    pub fn try_read_bytes(&mut self, bytes: &mut [u8]) -> ByteReult<usize> {
+        let n = self.try_get_bytes(bytes)?;
+        self.read_index += n;
+        Ok(n)
+    }
+
+    pub fn try_read_bool(&mut self) -> ByteReult<bool> {
+        self.try_read_as::<bool, BigEndian>()
+    }
+
+    pub fn try_read_u8(&mut self) -> ByteReult<u8> {
+        self.try_read_as::<u8, BigEndian>()
+    }
+
+    pub fn try_read_i8(&mut self) -> ByteReult<i8> {
+        self.try_read_as::<i8, BigEndian>()
+    }
+
+    pub fn try_read_u16_be(&mut self) -> ByteReult<u16> {
+        self.try_read_as::<u16, BigEndian>()
+    }
+
+    pub fn try_read_u16_le(&mut self) -> ByteReult<u16> {
+        self.try_read_as::<u16, LittleEndian>()
+    }
+
+    pub fn try_read_i16_be(&mut self) -> ByteReult<i16> {
+        self.try_read_as::<i16, BigEndian>()
+    }
+
+    pub fn try_read_i16_le(&mut self) -> ByteReult<i16> {
+        self.try_read_as::<i16, LittleEndian>()
+    }
+
+    pub fn try_read_u32_be(&mut self) -> ByteReult<u32> {
+        self.try_read_as::<u32, BigEndian>()
+    }
+
+    pub fn try_read_u32_le(&mut self) -> ByteReult<u32> {
+        self.try_read_as::<u32, LittleEndian>()
+    }
+
+    pub fn try_read_i32_be(&mut self) -> ByteReult<i32> {
+        self.try_read_as::<i32, BigEndian>()
+    }
+
+    pub fn try_read_i32_le(&mut self) -> ByteReult<i32> {
+        self.try_read_as::<i32, LittleEndian>()
+    }
+
+    pub fn try_read_u64_be(&mut self) -> ByteReult<u64> {
+        self.try_read_as::<u64, BigEndian>()
+    }
+
+    pub fn try_read_u64_le(&mut self) -> ByteReult<u64> {
+        self.try_read_as::<u64, LittleEndian>()
+    }
+
+    pub fn try_read_i64_be(&mut self) -> ByteReult<i64> {
+        self.try_read_as::<i64, BigEndian>()
+    }
+
+    pub fn try_read_i64_le(&mut self) -> ByteReult<i64> {
+        self.try_read_as::<i64, LittleEndian>()
+    }
+
+    pub fn try_read_u128_be(&mut self) -> ByteReult<u128> {
+        self.try_read_as::<u128, BigEndian>()
+    }
+
+    pub fn try_read_u128_le(&mut self) -> ByteReult<u128> {
+        self.try_read_as::<u128, LittleEndian>()
+    }
+
+    pub fn try_read_i128_be(&mut self) -> ByteReult<i128> {
+        self.try_read_as::<i128, BigEndian>()
+    }
+
+    pub fn try_read_i128_le(&mut self) -> ByteReult<i128> {
+        self.try_read_as::<i128, LittleEndian>()
+    }
+
+    pub fn try_read_f32_be(&mut self) -> ByteReult<f32> {
+        self.try_read_as::<f32, BigEndian>()
+    }
+
+    pub fn try_read_f32_le(&mut self) -> ByteReult<f32> {
+        self.try_read_as::<f32, LittleEndian>()
+    }
+
+    pub fn try_read_f64_be(&mut self) -> ByteReult<f64> {
+        self.try_read_as::<f64, BigEndian>()
+    }
+
+    pub fn try_read_f64_le(&mut self) -> ByteReult<f64> {
+        self.try_read_as::<f64, LittleEndian>()
+    }
+
+    // try read end
+
+
 
     pub fn write_str(&mut self, v: &str) -> io::Result<usize> {
-        let result = self.buf.write(v.as_bytes());
-        self.write_index += v.as_bytes().len();
-        result
+        self.write_bytes(v.as_bytes())
     }
 
 
     pub fn write_bytebuf(&mut self, bb: ByteBuf) -> io::Result<usize> {
-        let result = self.buf.write(bb.as_slice());
-        self.write_index += bb.as_slice().len();
-        result
+        self.write_bytes(bb.as_slice())
     }
 
 
     pub fn write_bytes(&mut self, bytes: &[u8]) -> io::Result<usize> {
-        let result = self.buf.write(bytes);
+        self.maybe_discard_read_bytes();
+        self.ensure_writable(bytes.len())?;
+        self.buf.append(bytes, self.capacity);
         self.write_index += bytes.len();
-        result
-    }
-
-    fn write_string(&mut self, v: String) -> io::Result<usize> {
-        let result = self.write_bytes(v.as_bytes());
-        result
+        Ok(bytes.len())
     }
 
-
     // u8 bigendian same as smallendian
 
     pub fn write_string_with_u8_be_len(&mut self, v: String) -> ByteReult<usize> {
-        if v.as_bytes().len() > u8::MAX as usize {
-            return Err(ByteBufError::new("StringLength out of  u8 max_value ".to_string()));
-        }
-        // write length
-        self.write_u8_be(v.as_bytes().len() as u8);
-        // write string
-        if let Ok(l) = self.write_string(v) {
-            Ok(l)
-        } else {
-            return Err(ByteBufError::new("nothing to write".to_string()));
-        }
+        self.write_string_with_len(LengthPrefix::U8, &v)
     }
 
 
     pub fn write_string_with_u16_be_len(&mut self, v: String) -> ByteReult<usize> {
-        if v.as_bytes().len() > u16::MAX as usize {
-            return Err(ByteBufError::new("StringLength out of  u8 max_value ".to_string()));
-        }
-        // write length
-        self.write_u16_be(v.as_bytes().len() as u16);
-        // write string
-        if let Ok(l) = self.write_string(v) {
-            Ok(l)
-        } else {
-            return Err(ByteBufError::new("nothing to write".to_string()));
-        }
+        self.write_string_with_len(LengthPrefix::U16Be, &v)
     }
 
     pub fn write_string_with_u16_le_len(&mut self, v: String) -> ByteReult<usize> {
-        if v.as_bytes().len() > u16::MAX as usize {
-            return Err(ByteBufError::new("StringLength out of  u8 max_value ".to_string()));
-        }
-        // write length
-        self.write_u16_le(v.as_bytes().len() as u16);
-        // write string
-        if let Ok(l) = self.write_string(v) {
-            Ok(l)
-        } else {
-            return Err(ByteBufError::new("nothing to write".to_string()));
-        }
+        self.write_string_with_len(LengthPrefix::U16Le, &v)
     }
 
 
     pub fn write_string_with_u32_be_len(&mut self, v: String) -> ByteReult<usize> {
-        if v.as_bytes().len() > u16::MAX as usize {
-            return Err(ByteBufError::new("StringLength out of  u8 max_value ".to_string()));
-        }
-        // write length
-        self.write_u32_be(v.as_bytes().len() as u32);
-        // write string
-        if let Ok(l) = self.write_string(v) {
-            Ok(l)
-        } else {
-            return Err(ByteBufError::new("nothing to write".to_string()));
-        }
+        self.write_string_with_len(LengthPrefix::U32Be, &v)
     }
 
     pub fn write_string_with_u32_le_len(&mut self, v: String) -> ByteReult<usize> {
-        if v.as_bytes().len() > u16::MAX as usize {
-            return Err(ByteBufError::new("StringLength out of  u8 max_value ".to_string()));
-        }
-        // write length
-        self.write_u32_le(v.as_bytes().len() as u32);
-        // write string
-        if let Ok(l) = self.write_string(v) {
-            Ok(l)
-        } else {
-            return Err(ByteBufError::new("nothing to write".to_string()));
-        }
+        self.write_string_with_len(LengthPrefix::U32Le, &v)
     }
 
 
     pub fn write_bool(&mut self, v: bool) -> io::Result<usize> {
-        let r = if v {
-            self.buf.write(&[0x01])
-        } else {
-            self.buf.write(&[0x00])
-        };
-        self.write_index += 1;
-        r
+        self.write_as::<bool, BigEndian>(v)
     }
 
     pub fn write_u8_be(&mut self, v: u8) -> io::Result<usize> {
-        let r = self.buf.write(&v.to_be_bytes());
-        self.write_index += 1;
-        r
+        self.write_as::<u8, BigEndian>(v)
     }
 
     pub fn write_u8_le(&mut self, v: u8) -> io::Result<usize> {
-        let r = self.buf.write(&v.to_le_bytes());
-        self.write_index += 1;
-        r
+        self.write_as::<u8, LittleEndian>(v)
     }
 
     pub fn write_i8_be(&mut self, v: i8) -> io::Result<usize> {
-        let r = self.buf.write(&v.to_be_bytes());
-        self.write_index += 1;
-        r
+        self.write_as::<i8, BigEndian>(v)
     }
 
     pub fn write_i8_le(&mut self, v: i8) -> io::Result<usize> {
-        let r = self.buf.write(&v.to_le_bytes());
-        self.write_index += 1;
-        r
+        self.write_as::<i8, LittleEndian>(v)
     }
 
     pub fn write_u16_be(&mut self, v: u16) -> io::Result<usize> {
-        let r = self.buf.write(&v.to_be_bytes());
-        self.write_index += mem::size_of::<u16>();
-        r
+        self.write_as::<u16, BigEndian>(v)
     }
 
     pub fn write_u16_le(&mut self, v: u16) -> io::Result<usize> {
-        let r = self.buf.write(&v.to_le_bytes());
-        self.write_index += mem::size_of::<u16>();
-        r
+        self.write_as::<u16, LittleEndian>(v)
     }
 
     pub fn write_i16_be(&mut self, v: i16) -> io::Result<usize> {
-        let r = self.buf.write(&v.to_be_bytes());
-        self.write_index += mem::size_of::<i16>();
-        r
+        self.write_as::<i16, BigEndian>(v)
     }
 
     pub fn write_i16_le(&mut self, v: i16) -> io::Result<usize> {
-        let r = self.buf.write(&v.to_le_bytes());
-        self.write_index += mem::size_of::<i16>();
-        r
+        self.write_as::<i16, LittleEndian>(v)
     }
 
 
     pub fn write_u32_be(&mut self, v: u32) -> io::Result<usize> {
-        let r = self.buf.write(&v.to_be_bytes());
-        self.write_index += mem::size_of::<u32>();
-        r
+        self.write_as::<u32, BigEndian>(v)
     }
 
     pub fn write_u32_le(&mut self, v: u32) -> io::Result<usize> {
-        let r = self.buf.write(&v.to_le_bytes());
-        self.write_index += mem::size_of::<u32>();
-        r
+        self.write_as::<u32, LittleEndian>(v)
     }
 
 
     pub fn write_i32_be(&mut self, v: i32) -> io::Result<usize> {
-        let r = self.buf.write(&v.to_be_bytes());
-        self.write_index += mem::size_of::<i32>();
-        r
+        self.write_as::<i32, BigEndian>(v)
     }
 
     pub fn write_i32_le(&mut self, v: i32) -> io::Result<usize> {
-        let r = self.buf.write(&v.to_le_bytes());
-        self.write_index += mem::size_of::<i32>();
-        r
+        self.write_as::<i32, LittleEndian>(v)
     }
 
 
     pub fn write_u64_be(&mut self, v: u64) -> io::Result<usize> {
-        let r = self.buf.write(&v.to_be_bytes());
-        self.write_index += mem::size_of::<u64>();
-        r
+        self.write_as::<u64, BigEndian>(v)
     }
 
     pub fn write_u64_le(&mut self, v: u64) -> io::Result<usize> {
-        let r = self.buf.write(&v.to_le_bytes());
-        self.write_index += mem::size_of::<u64>();
-        r
+        self.write_as::<u64, LittleEndian>(v)
     }
 
     pub fn write_i64_be(&mut self, v: i64) -> io::Result<usize> {
-        let r = self.buf.write(&v.to_be_bytes());
-        self.write_index += mem::size_of::<i64>();
-        r
+        self.write_as::<i64, BigEndian>(v)
     }
     pub fn write_i64_le(&mut self, v: i64) -> io::Result<usize> {
-        let r = self.buf.write(&v.to_le_bytes());
-        self.write_index += mem::size_of::<i64>();
-        r
+        self.write_as::<i64, LittleEndian>(v)
     }
 
 
     pub fn write_u128_be(&mut self, v: u128) -> io::Result<usize> {
-        let r = self.buf.write(&v.to_be_bytes());
-        self.write_index += mem::size_of::<u128>();
-
-        r
+        self.write_as::<u128, BigEndian>(v)
     }
 
     pub fn write_u128_le(&mut self, v: u128) -> io::Result<usize> {
-        let r = self.buf.write(&v.to_le_bytes());
-        self.write_index += mem::size_of::<u128>();
-
-        r
+        self.write_as::<u128, LittleEndian>(v)
     }
 
     pub fn write_i128_be(&mut self, v: i128) -> io::Result<usize> {
-        let r = self.buf.write(&v.to_be_bytes());
-        self.write_index += mem::size_of::<i128>();
-        r
+        self.write_as::<i128, BigEndian>(v)
     }
 
     pub fn write_i128_le(&mut self, v: i128) -> io::Result<usize> {
-        let r = self.buf.write(&v.to_le_bytes());
-        self.write_index += mem::size_of::<i128>();
-        r
+        self.write_as::<i128, LittleEndian>(v)
     }
 
     pub fn write_f32_be(&mut self, v: f32) -> io::Result<usize> {
-        let r = self.buf.write(&v.to_be_bytes());
-        self.write_index += mem::size_of::<f32>();
-        r
+        self.write_as::<f32, BigEndian>(v)
     }
 
     pub fn write_f32_le(&mut self, v: f32) -> io::Result<usize> {
-        let r = self.buf.write(&v.to_le_bytes());
-        self.write_index += mem::size_of::<f32>();
-        r
+        self.write_as::<f32, LittleEndian>(v)
     }
 
 
     pub fn write_f64_be(&mut self, v: f64) -> io::Result<usize> {
-        let r = self.buf.write(&v.to_be_bytes());
-        self.write_index += mem::size_of::<f64>();
-        r
+        self.write_as::<f64, BigEndian>(v)
     }
 
     pub fn write_f64_le(&mut self, v: f64) -> io::Result<usize> {
-        let r = self.buf.write(&v.to_le_bytes());
-        self.write_index += mem::size_of::<f64>();
-        r
+        self.write_as::<f64, LittleEndian>(v)
     }
 
 
     pub fn skip_index(&mut self, n: usize) -> Result<usize, ByteBufError> {
-        if n > self.write_index {
-            let result1 = Err(ByteBufError {
-                message: "exceed writeIndex range ".to_string()
-            });
-            return result1;
-        };
+        self.check_readable(n)?;
         self.read_index += n;
         Ok(self.read_index)
     }
 
+    /// Moves the unread region to the front of the buffer, shifting indices and marks with it.
+    pub fn discard_read_bytes(&mut self) {
+        self.discard_bytes_before(self.read_index);
+    }
+
+    // drops the first n bytes, which must not be past the reader index
+    fn discard_bytes_before(&mut self, n: usize) {
+        if n == 0 {
+            return;
+        }
+        self.buf.discard_front(n, self.capacity);
+        self.read_index -= n;
+        self.write_index -= n;
+        if self.read_mark != -1 {
+            self.read_mark = (self.read_mark - n as isize).max(0);
+        }
+        if self.write_mark != -1 {
+            self.write_mark = (self.write_mark - n as isize).max(0);
+        }
+    }
+
+    /// Like `discard_read_bytes`, but only once everything was read or at least half of the buffer is consumed.
+    pub fn discard_some_read_bytes(&mut self) {
+        if self.read_index == self.write_index || self.read_index >= self.buf.len() / 2 {
+            self.discard_read_bytes();
+        }
+    }
+
+    /// When set, writes first discard the consumed bytes once the reader index reaches `threshold`.
+    /// Bytes from a reader mark on are kept.
+    pub fn set_discard_threshold(&mut self, threshold: Option<usize>) {
+        self.discard_threshold = threshold;
+    }
+
+    pub fn discard_threshold(&self) -> Option<usize> {
+        self.discard_threshold
+    }
+
+    // unlike discard_read_bytes this keeps the bytes from a live reader mark on,
+    // so the mark can still be reset to afterwards
+    fn maybe_discard_read_bytes(&mut self) {
+        if let Some(threshold) = self.discard_threshold {
+            if self.read_index >= threshold {
+                let n = match self.read_mark {
+                    -1 => self.read_index,
+                    mark => self.read_index.min(mark as usize),
+                };
+                self.discard_bytes_before(n);
+            }
+        }
+    }
+
     pub fn readable_bytes(&self) -> usize {
         self.get_writer_index() - self.get_reader_index()
     }
 
 
     pub fn available_bytes(&self) -> &[u8] {
-        return &self.buf[..self.get_writer_index()];
+        &self.buf[..self.get_writer_index()]
     }
 
     pub fn is_readable(&self) -> bool {
-        let r = if self.readable_bytes() > 0 {
-            true
-        } else {
-            false
-        };
-        r
+        self.readable_bytes() > 0
     }
 
 
     pub fn is_writable(&self) -> bool {
-        let r = if (self.capacity - self.write_index) > 0 {
-            true
-        } else {
-            false
-        };
-        r
+        self.writable_bytes() > 0
     }
 
     pub fn get_writer_index(&self) -> usize {
@@ -1048,22 +1047,38 @@ impl ByteBuf {
         }
     }
 
-    pub fn as_slice(&self) -> &[u8] {
-        return self.buf.as_slice();
+    pub fn mark_writer_index(&mut self) {
+        self.write_mark = self.write_index as isize
     }
 
+    /// Moves the writer index to `v`, between the reader index and the capacity.
+    /// Moving it back drops everything written after `v`; moving it forward
+    /// reserves zero-filled space that can be filled in later with the `set_*` methods.
+    pub fn set_writer_index(&mut self, v: usize) -> ByteReult<()> {
+        let bound = self.capacity;
+        if v < self.read_index || v > bound {
+            return Err(ByteBufError::IndexOutOfBounds { index: v, len: 0, bound });
+        }
+        self.buf.resize(v, self.capacity);
+        self.write_index = v;
+        Ok(())
+    }
+
+    /// Rolls the writer index back to the mark, truncating whatever was written since.
+    pub fn reset_writer_index(&mut self) {
+        if self.write_mark != -1 {
+            let mark = self.write_mark as usize;
+            self.buf.resize(mark, self.capacity);
+            self.write_index = mark;
+            self.read_index = self.read_index.min(mark);
+            self.write_mark = -1;
+        }
+    }
 
-    pub fn print_bytes(&self) {
-        let mut i = 1;
-        self.buf.iter().for_each(|x| {
-            print!("{}", x);
-            if i % 20 == 0 {
-                println!()
-            }
-            i = i + 1;
-        });
-        println!()
+    pub fn as_slice(&self) -> &[u8] {
+        &self.buf
     }
+
 }
 
 
@@ -0,0 +1,333 @@
+use std::cell::RefCell;
+use std::collections::HashMap;
+use std::fmt::{self, Debug, Formatter};
+use std::ops::{Deref, DerefMut};
+use std::sync::{Arc, Mutex, Weak};
+use std::sync::atomic::{AtomicU64, AtomicUsize, Ordering};
+
+use crate::bytebuf::{ByteBuf, Endian, GrowthStrategy, DEFAULT_MAX_CAPACITY};
+
+// the smallest size class, 256 bytes
+const MIN_CLASS_SHIFT: u32 = 8;
+
+static NEXT_POOL_ID: AtomicU64 = AtomicU64::new(0);
+
+thread_local! {
+    // per-thread free lists, keyed by pool id
+    static LOCAL_CACHES: RefCell<HashMap<u64, LocalCache>> = RefCell::new(HashMap::new());
+}
+
+/// Something that hands out buffers of at least a given capacity.
+pub trait ByteBufAllocator {
+    fn buffer(&self, capacity: usize) -> PooledByteBuf;
+}
+
+/// An allocator that always allocates a fresh buffer and drops it when done.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct Unpooled;
+
+impl ByteBufAllocator for Unpooled {
+    fn buffer(&self, capacity: usize) -> PooledByteBuf {
+        PooledByteBuf { buf: Some(ByteBuf::new_with_capacity(capacity)), pool: None }
+    }
+}
+
+/// Limits of a `ByteBufPool`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct PoolConfig {
+    /// larger requests are allocated and freed as usual
+    pub max_pooled_size: usize,
+    /// free buffers each thread keeps per size class
+    pub local_cache_size: usize,
+    /// free buffers the shared pool keeps per size class
+    pub shared_cache_size: usize,
+}
+
+impl Default for PoolConfig {
+    fn default() -> Self {
+        PoolConfig {
+            max_pooled_size: 1 << 20,
+            local_cache_size: 16,
+            shared_cache_size: 256,
+        }
+    }
+}
+
+/// A snapshot of what a pool has done so far.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct PoolStats {
+    /// requests served from a free list
+    pub hits: usize,
+    /// requests that had to allocate
+    pub misses: usize,
+    /// bytes currently held in free lists
+    pub bytes_retained: usize,
+    /// the most `bytes_retained` has ever been
+    pub high_water_mark: usize,
+}
+
+/// Recycles buffers through power-of-two size classes, from 256 bytes up to
+/// `PoolConfig::max_pooled_size`.
+///
+/// A returned buffer goes to the free list of the current thread first and to
+/// the shared one once that is full. Clones of a pool share its free lists.
+#[derive(Clone)]
+pub struct ByteBufPool {
+    inner: Arc<PoolInner>,
+}
+
+struct PoolInner {
+    id: u64,
+    config: PoolConfig,
+    shared: Vec<Mutex<Vec<ByteBuf>>>,
+    hits: AtomicUsize,
+    misses: AtomicUsize,
+    bytes_retained: AtomicUsize,
+    high_water_mark: AtomicUsize,
+}
+
+struct LocalCache {
+    pool: Weak<PoolInner>,
+    classes: Vec<Vec<ByteBuf>>,
+}
+
+impl Drop for LocalCache {
+    fn drop(&mut self) {
+        if let Some(pool) = self.pool.upgrade() {
+            let bytes = self.classes.iter().flatten().map(|b| b.buf.allocated()).sum();
+            pool.bytes_retained.fetch_sub(bytes, Ordering::Relaxed);
+        }
+    }
+}
+
+impl ByteBufPool {
+    pub fn new() -> Self {
+        ByteBufPool::with_config(PoolConfig::default())
+    }
+
+    pub fn with_config(config: PoolConfig) -> Self {
+        let classes = class_of(config.max_pooled_size).map_or(0, |c| c + 1);
+        ByteBufPool {
+            inner: Arc::new(PoolInner {
+                id: NEXT_POOL_ID.fetch_add(1, Ordering::Relaxed),
+                config,
+                shared: (0..classes).map(|_| Mutex::new(Vec::new())).collect(),
+                hits: AtomicUsize::new(0),
+                misses: AtomicUsize::new(0),
+                bytes_retained: AtomicUsize::new(0),
+                high_water_mark: AtomicUsize::new(0),
+            }),
+        }
+    }
+
+    pub fn config(&self) -> PoolConfig {
+        self.inner.config
+    }
+
+    pub fn stats(&self) -> PoolStats {
+        let inner = &self.inner;
+        PoolStats {
+            hits: inner.hits.load(Ordering::Relaxed),
+            misses: inner.misses.load(Ordering::Relaxed),
+            bytes_retained: inner.bytes_retained.load(Ordering::Relaxed),
+            high_water_mark: inner.high_water_mark.load(Ordering::Relaxed),
+        }
+    }
+}
+
+impl Default for ByteBufPool {
+    fn default() -> Self {
+        ByteBufPool::new()
+    }
+}
+
+impl Debug for ByteBufPool {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        f.debug_struct("ByteBufPool")
+            .field("config", &self.inner.config)
+            .field("stats", &self.stats())
+            .finish()
+    }
+}
+
+impl ByteBufAllocator for ByteBufPool {
+    fn buffer(&self, capacity: usize) -> PooledByteBuf {
+        let inner = &self.inner;
+        let class = class_for(capacity).filter(|&c| c < inner.shared.len());
+        let buf = match class.and_then(|c| inner.take(c)) {
+            Some(buf) => {
+                inner.hits.fetch_add(1, Ordering::Relaxed);
+                buf
+            }
+            None => {
+                inner.misses.fetch_add(1, Ordering::Relaxed);
+                let size = class.map_or(capacity, class_size);
+                ByteBuf::new_with_capacity(size)
+            }
+        };
+        PooledByteBuf { buf: Some(buf), pool: Some(Arc::clone(inner)) }
+    }
+}
+
+impl PoolInner {
+    fn take(self: &Arc<Self>, class: usize) -> Option<ByteBuf> {
+        let buf = with_local_caches(|caches| caches.get_mut(&self.id).and_then(|c| c.classes[class].pop()))
+            .flatten()
+            .or_else(|| self.shared[class].lock().unwrap().pop())?;
+        self.bytes_retained.fetch_sub(buf.buf.allocated(), Ordering::Relaxed);
+        Some(buf)
+    }
+
+    fn give_back(self: &Arc<Self>, mut buf: ByteBuf) {
+        if !buf.recycle() {
+            return;
+        }
+        let size = buf.buf.allocated();
+        let class = match class_of(size) {
+            Some(class) if class < self.shared.len() => class,
+            _ => return,
+        };
+        // counted up front so a concurrent take never sees it missing
+        let retained = self.bytes_retained.fetch_add(size, Ordering::Relaxed) + size;
+        // hands the buffer back when the local cache is full
+        let overflow = with_local_caches(|caches| {
+            let cache = caches.entry(self.id).or_insert_with(|| LocalCache {
+                pool: Arc::downgrade(self),
+                classes: (0..self.shared.len()).map(|_| Vec::new()).collect(),
+            });
+            let list = &mut cache.classes[class];
+            if list.len() < self.config.local_cache_size {
+                list.push(buf);
+                None
+            } else {
+                Some(buf)
+            }
+        });
+        let kept = match overflow {
+            Some(None) => true,
+            Some(Some(buf)) => {
+                let mut list = self.shared[class].lock().unwrap();
+                let room = list.len() < self.config.shared_cache_size;
+                if room {
+                    list.push(buf);
+                }
+                room
+            }
+            // the thread is going away, let the buffer go with it
+            None => false,
+        };
+        if !kept {
+            self.bytes_retained.fetch_sub(size, Ordering::Relaxed);
+            return;
+        }
+        self.high_water_mark.fetch_max(retained, Ordering::Relaxed);
+    }
+}
+
+// runs `f` on this thread's caches after dropping those of pools that are gone;
+// a pool only clears the caches of the thread it is dropped on, the others are
+// cleared here the next time their thread uses any pool. None once the thread is
+// shutting down
+fn with_local_caches<R>(f: impl FnOnce(&mut HashMap<u64, LocalCache>) -> R) -> Option<R> {
+    LOCAL_CACHES
+        .try_with(|caches| {
+            let mut caches = caches.borrow_mut();
+            caches.retain(|_, cache| cache.pool.strong_count() > 0);
+            f(&mut caches)
+        })
+        .ok()
+}
+
+// the pools with a cache on this thread
+#[cfg(test)]
+pub(crate) fn local_cache_count() -> usize {
+    LOCAL_CACHES.with(|caches| caches.borrow().len())
+}
+
+impl Drop for PoolInner {
+    fn drop(&mut self) {
+        let id = self.id;
+        let _ = LOCAL_CACHES.try_with(|caches| caches.try_borrow_mut().map(|mut caches| caches.remove(&id)));
+    }
+}
+
+// the class whose buffers can hold `capacity` bytes
+fn class_for(capacity: usize) -> Option<usize> {
+    let size = capacity.max(1 << MIN_CLASS_SHIFT).checked_next_power_of_two()?;
+    Some((size.trailing_zeros() - MIN_CLASS_SHIFT) as usize)
+}
+
+// the largest class a buffer of `size` bytes can serve
+fn class_of(size: usize) -> Option<usize> {
+    if size < 1 << MIN_CLASS_SHIFT {
+        return None;
+    }
+    Some((usize::BITS - 1 - size.leading_zeros() - MIN_CLASS_SHIFT) as usize)
+}
+
+fn class_size(class: usize) -> usize {
+    1 << (class as u32 + MIN_CLASS_SHIFT)
+}
+
+impl ByteBuf {
+    // empties the buffer for its next user; false when some slice still shares its storage
+    fn recycle(&mut self) -> bool {
+        match self.buf.unique_vec() {
+            Some(vec) => {
+                vec.clear();
+                self.capacity = vec.capacity();
+            }
+            None => return false,
+        }
+        self.max_capacity = DEFAULT_MAX_CAPACITY;
+        self.growth = GrowthStrategy::default();
+        self.read_mark = -1;
+        self.write_mark = -1;
+        self.read_index = 0;
+        self.write_index = 0;
+        self.discard_threshold = None;
+        self.endian = Endian::Big;
+        true
+    }
+}
+
+/// A buffer from a `ByteBufAllocator`, going back to its pool when dropped.
+pub struct PooledByteBuf {
+    buf: Option<ByteBuf>,
+    pool: Option<Arc<PoolInner>>,
+}
+
+impl PooledByteBuf {
+    /// Keeps the buffer for good instead of returning it to the pool.
+    pub fn into_inner(mut self) -> ByteBuf {
+        self.buf.take().unwrap()
+    }
+}
+
+impl Deref for PooledByteBuf {
+    type Target = ByteBuf;
+
+    fn deref(&self) -> &ByteBuf {
+        self.buf.as_ref().unwrap()
+    }
+}
+
+impl DerefMut for PooledByteBuf {
+    fn deref_mut(&mut self) -> &mut ByteBuf {
+        self.buf.as_mut().unwrap()
+    }
+}
+
+impl Debug for PooledByteBuf {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        Debug::fmt(&**self, f)
+    }
+}
+
+impl Drop for PooledByteBuf {
+    fn drop(&mut self) {
+        if let (Some(buf), Some(pool)) = (self.buf.take(), self.pool.take()) {
+            pool.give_back(buf);
+        }
+    }
+}
@@ -0,0 +1,119 @@
+use std::io;
+use std::mem;
+
+use crate::bytebuf::{ByteBuf, ByteReult};
+use crate::bytebuf::endian::{ByteOrder, Endian};
+
+/// A value with a fixed encoded width that can be read from a `ByteBuf` in either byte order.
+pub trait FromBytes: Sized {
+    const SIZE: usize;
+
+    /// `bytes` is exactly `SIZE` long.
+    fn from_bytes<E: ByteOrder>(bytes: &[u8]) -> Self;
+}
+
+/// A value with a fixed encoded width that can be written to a `ByteBuf` in either byte order.
+pub trait ToBytes {
+    const SIZE: usize;
+
+    /// `out` is exactly `SIZE` long.
+    fn to_bytes<E: ByteOrder>(&self, out: &mut [u8]);
+}
+
+macro_rules! impl_primitive {
+    ($($t:ty),*) => {
+        $(
+            impl FromBytes for $t {
+                const SIZE: usize = mem::size_of::<$t>();
+
+                fn from_bytes<E: ByteOrder>(bytes: &[u8]) -> Self {
+                    let mut x = [0u8; mem::size_of::<$t>()];
+                    x.copy_from_slice(bytes);
+                    match E::ENDIAN {
+                        Endian::Big => <$t>::from_be_bytes(x),
+                        Endian::Little => <$t>::from_le_bytes(x),
+                    }
+                }
+            }
+
+            impl ToBytes for $t {
+                const SIZE: usize = mem::size_of::<$t>();
+
+                fn to_bytes<E: ByteOrder>(&self, out: &mut [u8]) {
+                    let x = match E::ENDIAN {
+                        Endian::Big => self.to_be_bytes(),
+                        Endian::Little => self.to_le_bytes(),
+                    };
+                    out.copy_from_slice(&x);
+                }
+            }
+        )*
+    };
+}
+
+impl_primitive!(u8, i8, u16, i16, u32, i32, u64, i64, u128, i128, f32, f64);
+
+impl FromBytes for bool {
+    const SIZE: usize = 1;
+
+    fn from_bytes<E: ByteOrder>(bytes: &[u8]) -> Self {
+        bytes[0] == 0x01u8
+    }
+}
+
+impl ToBytes for bool {
+    const SIZE: usize = 1;
+
+    fn to_bytes<E: ByteOrder>(&self, out: &mut [u8]) {
+        out[0] = *self as u8;
+    }
+}
+
+// values up to this size are encoded on the stack
+pub(crate) const INLINE_SIZE: usize = 16;
+
+// encodes `v` into a scratch buffer and hands that to `f`
+fn with_encoded<T: ToBytes, E: ByteOrder, R>(v: &T, f: impl FnOnce(&mut [u8]) -> R) -> R {
+    if T::SIZE <= INLINE_SIZE {
+        let mut bytes = [0u8; INLINE_SIZE];
+        v.to_bytes::<E>(&mut bytes[..T::SIZE]);
+        f(&mut bytes[..T::SIZE])
+    } else {
+        let mut bytes = vec![0u8; T::SIZE];
+        v.to_bytes::<E>(&mut bytes);
+        f(&mut bytes)
+    }
+}
+
+impl ByteBuf {
+    pub fn get_as<T: FromBytes, E: ByteOrder>(&self) -> T {
+        T::from_bytes::<E>(&self.buf[self.read_index..self.read_index + T::SIZE])
+    }
+
+    pub fn try_get_as<T: FromBytes, E: ByteOrder>(&self) -> ByteReult<T> {
+        self.check_readable(T::SIZE)?;
+        Ok(self.get_as::<T, E>())
+    }
+
+    pub fn read_as<T: FromBytes, E: ByteOrder>(&mut self) -> T {
+        let r = self.get_as::<T, E>();
+        self.read_index += T::SIZE;
+        r
+    }
+
+    pub fn try_read_as<T: FromBytes, E: ByteOrder>(&mut self) -> ByteReult<T> {
+        let r = self.try_get_as::<T, E>()?;
+        self.read_index += T::SIZE;
+        Ok(r)
+    }
+
+    pub fn write_as<T: ToBytes, E: ByteOrder>(&mut self, v: T) -> io::Result<usize> {
+        with_encoded::<T, E, _>(&v, |bytes| self.write_bytes(bytes))
+    }
+
+    pub fn set_at<T: ToBytes, E: ByteOrder>(&mut self, wid: usize, v: T) -> ByteReult<()> {
+        self.check_index(wid, T::SIZE)?;
+        with_encoded::<T, E, _>(&v, |bytes| self.copy_from_slice(bytes, wid));
+        Ok(())
+    }
+}
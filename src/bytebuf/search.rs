@@ -0,0 +1,90 @@
+use std::ops::{Bound, Range, RangeBounds};
+
+use crate::bytebuf::ByteBuf;
+
+// processors for `for_each_byte`, which stops at the first byte they return false for
+
+/// Stops at a `\r` or `\n`.
+pub const FIND_CRLF: fn(u8) -> bool = |b| b != b'\r' && b != b'\n';
+/// Stops at a zero byte.
+pub const FIND_NUL: fn(u8) -> bool = |b| b != 0;
+/// Stops at the first byte that is not ASCII whitespace.
+pub const FIND_NON_WHITESPACE: fn(u8) -> bool = |b| b.is_ascii_whitespace();
+
+// Boyer-Moore-Horspool; single bytes take the plain scan
+pub(crate) fn find(haystack: &[u8], needle: &[u8]) -> Option<usize> {
+    let n = needle.len();
+    match n {
+        0 => return Some(0),
+        1 => return haystack.iter().position(|&b| b == needle[0]),
+        _ if n > haystack.len() => return None,
+        _ => {}
+    }
+    let mut skip = [n; 256];
+    for (i, &b) in needle[..n - 1].iter().enumerate() {
+        skip[b as usize] = n - 1 - i;
+    }
+    let last = needle[n - 1];
+    let mut i = 0;
+    while i + n <= haystack.len() {
+        let b = haystack[i + n - 1];
+        if b == last && haystack[i..i + n - 1] == needle[..n - 1] {
+            return Some(i);
+        }
+        i += skip[b as usize];
+    }
+    None
+}
+
+impl ByteBuf {
+    // clamps an index into the readable region
+    fn clamp_readable(&self, index: usize) -> usize {
+        index.clamp(self.read_index, self.write_index)
+    }
+
+    // resolves `range` to absolute indices within the readable region
+    pub(crate) fn readable_range<R: RangeBounds<usize>>(&self, range: R) -> Range<usize> {
+        let start = match range.start_bound() {
+            Bound::Included(&i) => i,
+            Bound::Excluded(&i) => i.saturating_add(1),
+            Bound::Unbounded => self.read_index,
+        };
+        let end = match range.end_bound() {
+            Bound::Included(&i) => i.saturating_add(1),
+            Bound::Excluded(&i) => i,
+            Bound::Unbounded => self.write_index,
+        };
+        let (start, end) = (self.clamp_readable(start), self.clamp_readable(end));
+        start..end.max(start)
+    }
+
+    /// The first index of `byte` in `from..to`, or when `from > to` the last one in `to..from`.
+    /// Both ends are clamped to the readable bytes.
+    pub fn index_of(&self, from: usize, to: usize, byte: u8) -> Option<usize> {
+        let (from, to) = (self.clamp_readable(from), self.clamp_readable(to));
+        if from <= to {
+            self.buf[from..to].iter().position(|&b| b == byte).map(|i| from + i)
+        } else {
+            self.buf[to..from].iter().rposition(|&b| b == byte).map(|i| to + i)
+        }
+    }
+
+    /// The index where `needle` first starts in the readable bytes.
+    pub fn index_of_slice(&self, needle: &[u8]) -> Option<usize> {
+        find(&self.buf[self.read_index..self.write_index], needle).map(|i| self.read_index + i)
+    }
+
+    /// How many readable bytes come before the first `byte`.
+    pub fn bytes_before(&self, byte: u8) -> Option<usize> {
+        self.index_of(self.read_index, self.write_index, byte).map(|i| i - self.read_index)
+    }
+
+    /// Hands the readable bytes within `range` to `processor` in order until it
+    /// returns false, and returns the index of that byte.
+    pub fn for_each_byte<R, F>(&self, range: R, mut processor: F) -> Option<usize>
+        where R: RangeBounds<usize>, F: FnMut(u8) -> bool {
+        let range = self.readable_range(range);
+        let start = range.start;
+        self.buf[range].iter().position(|&b| !processor(b)).map(|i| start + i)
+    }
+}
@@ -0,0 +1,65 @@
+use crate::bytebuf::{ByteBuf, ByteReult};
+use crate::error::ByteBufError;
+
+// derived buffers share the storage of the buffer they come from and keep
+// their own indices; a derived buffer that is written to gets its own copy first.
+// the parent keeps appending in place past the bytes its views can see, and only
+// copies its written bytes when it writes below that or outgrows the allocation
+
+impl ByteBuf {
+    // wraps `index..index + len` of this buffer's storage as a new, fully readable buffer
+    fn derive(&self, index: usize, len: usize) -> ByteBuf {
+        ByteBuf {
+            buf: self.buf.slice(index, index + len),
+            capacity: len,
+            max_capacity: self.max_capacity,
+            growth: self.growth,
+            read_mark: -1,
+            write_mark: -1,
+            read_index: 0,
+            write_index: len,
+            discard_threshold: None,
+            endian: self.endian,
+        }
+    }
+
+    /// A view of `len` written bytes starting at `index`, without copying them.
+    ///
+    /// While the view is alive, appending to this buffer stays in place as long as
+    /// it fits the allocation. Setting bytes the view can see, discarding read bytes
+    /// or growing past the allocation copies this buffer's written bytes once, and
+    /// writing to the view copies the view's bytes.
+    pub fn slice(&self, index: usize, len: usize) -> ByteReult<ByteBuf> {
+        if index.saturating_add(len) > self.write_index {
+            return Err(ByteBufError::IndexOutOfBounds { index, len, bound: self.write_index });
+        }
+        Ok(self.derive(index, len))
+    }
+
+    /// Like `slice`, over the next `len` readable bytes, advancing the reader index past them.
+    ///
+    /// Slicing frames off a buffer that keeps being appended to costs no copies
+    /// until it outgrows its allocation; see `slice` for what does copy.
+    pub fn read_slice(&mut self, len: usize) -> ByteReult<ByteBuf> {
+        self.check_readable(len)?;
+        let slice = self.derive(self.read_index, len);
+        self.read_index += len;
+        Ok(slice)
+    }
+
+    /// A view of the readable bytes that holds its own reference to the storage,
+    /// so it stays valid however this buffer is modified or dropped afterwards.
+    pub fn retained_slice(&self) -> ByteBuf {
+        self.derive(self.read_index, self.readable_bytes())
+    }
+
+    /// A view of the whole buffer with the same indices, sharing its storage.
+    pub fn duplicate(&self) -> ByteBuf {
+        self.clone()
+    }
+
+    /// How many buffers currently share this buffer's storage, itself included.
+    pub fn ref_count(&self) -> usize {
+        self.buf.ref_count()
+    }
+}
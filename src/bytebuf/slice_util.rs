@@ -40,6 +40,7 @@ impl<T> ArrayCopy<T> for &mut [T] {
     }
 }
 
+#[cfg(test)]
 mod test {
     use crate::bytebuf::slice_util::ArrayCopy;
 
@@ -49,9 +50,9 @@ mod test {
         let x1 = s.clone().into_boxed_str();
         let mut x2 = x1.into_boxed_bytes();
         let mut x = x2.as_mut();
-        let mut byte_a = unsafe { s.as_bytes_mut() };
-        let mut byte_b = &mut vec![0u8; byte_a.len()][..];
-        x.copy_to(&mut byte_b, 1);
+        let byte_a = unsafe { s.as_bytes_mut() };
+        let byte_b = &mut vec![0u8; byte_a.len()][..];
+        x.copy_to(byte_b, 1);
         println!("{}", String::from_utf8_lossy(byte_b));
     }
 }
\ No newline at end of file
@@ -0,0 +1,50 @@
+use std::cmp;
+use std::io::{self, BufRead, Read, Seek, SeekFrom, Write};
+
+use crate::bytebuf::ByteBuf;
+
+impl Read for ByteBuf {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        let n = cmp::min(buf.len(), self.readable_bytes());
+        self.read_bytes(&mut buf[..n]);
+        Ok(n)
+    }
+}
+
+impl BufRead for ByteBuf {
+    fn fill_buf(&mut self) -> io::Result<&[u8]> {
+        Ok(&self.buf[self.read_index..self.write_index])
+    }
+
+    fn consume(&mut self, amt: usize) {
+        self.read_index = cmp::min(self.read_index + amt, self.write_index);
+    }
+}
+
+impl Write for ByteBuf {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        self.write_bytes(buf)
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+}
+
+// seeking moves the reader index, anywhere between 0 and the writer index
+impl Seek for ByteBuf {
+    fn seek(&mut self, pos: SeekFrom) -> io::Result<u64> {
+        let (base, offset) = match pos {
+            SeekFrom::Start(n) => (0i128, n as i128),
+            SeekFrom::End(n) => (self.write_index as i128, n as i128),
+            SeekFrom::Current(n) => (self.read_index as i128, n as i128),
+        };
+        let target = base + offset;
+        if target < 0 || target > self.write_index as i128 {
+            return Err(io::Error::new(io::ErrorKind::InvalidInput,
+                                      format!("seek to {} outside of 0..={}", target, self.write_index)));
+        }
+        self.read_index = target as usize;
+        Ok(target as u64)
+    }
+}
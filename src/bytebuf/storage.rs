@@ -0,0 +1,187 @@
+use std::cell::UnsafeCell;
+#[cfg(any(feature = "bytes", feature = "tokio"))]
+use std::mem::MaybeUninit;
+use std::ops::{Deref, Range};
+use std::slice;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Arc;
+
+// backing bytes of a ByteBuf, shared by every buffer sliced or duplicated from it.
+//
+// the buffer that made the allocation owns it and is the only one that ever
+// touches the Vec; slices and duplicates are read-only views of a window of it.
+// the owner keeps writing in place past `frozen`, the furthest byte any view can
+// see, so appending to a buffer that frames were sliced from copies nothing.
+// writes below that, and any write through a view, first copy the visible bytes
+pub(crate) struct Storage {
+    shared: Arc<Shared>,
+    // the allocation as it was when this view was made; unused by the owner
+    ptr: *const u8,
+    start: usize,
+    end: usize,
+    owner: bool,
+}
+
+struct Shared {
+    vec: UnsafeCell<Vec<u8>>,
+    frozen: AtomicUsize,
+}
+
+// views only read below `frozen` through their own pointer, while the owner only
+// touches the Vec through &mut Storage, writes at or past `frozen` while views
+// exist and never moves the allocation before `is_unique` says it is the last
+// one holding it
+unsafe impl Sync for Shared {}
+unsafe impl Send for Storage {}
+unsafe impl Sync for Storage {}
+
+impl Storage {
+    pub(crate) fn new(data: Vec<u8>) -> Self {
+        Storage {
+            ptr: data.as_ptr(),
+            start: 0,
+            end: data.len(),
+            shared: Arc::new(Shared { vec: UnsafeCell::new(data), frozen: AtomicUsize::new(0) }),
+            owner: true,
+        }
+    }
+
+    // the Vec, only ever looked at by the owner
+    fn vec(&self) -> &Vec<u8> {
+        debug_assert!(self.owner);
+        unsafe { &*self.shared.vec.get() }
+    }
+
+    // whether nothing else holds the allocation; get_mut's acquire makes every
+    // read by a view dropped on another thread happen before what follows
+    fn is_unique(&mut self) -> bool {
+        Arc::get_mut(&mut self.shared).is_some()
+    }
+
+    // a view of `start..end` of the allocation
+    fn view(&self, start: usize, end: usize) -> Storage {
+        self.shared.frozen.fetch_max(end, Ordering::AcqRel);
+        let ptr = if self.owner { self.vec().as_ptr() } else { self.ptr };
+        Storage { shared: Arc::clone(&self.shared), ptr, start, end, owner: false }
+    }
+
+    // a view of `start..end`, relative to this one, sharing the same allocation
+    pub(crate) fn slice(&self, start: usize, end: usize) -> Storage {
+        let base = if self.owner { 0 } else { self.start };
+        self.view(base + start, base + end)
+    }
+
+    pub(crate) fn ref_count(&self) -> usize {
+        Arc::strong_count(&self.shared)
+    }
+
+    // the Vec, free to change from `index` on and holding at least `capacity` bytes;
+    // the visible bytes are copied to a fresh allocation first when that is not so
+    fn vec_mut_from(&mut self, index: usize, capacity: usize) -> &mut Vec<u8> {
+        let unique = self.is_unique();
+        let in_place = self.owner && (unique || index >= self.shared.frozen.load(Ordering::Acquire));
+        if !in_place || (!unique && self.vec().capacity() < capacity) {
+            let mut data = Vec::with_capacity(capacity.max(self.len()));
+            data.extend_from_slice(self);
+            *self = Storage::new(data);
+        } else if unique {
+            self.shared.frozen.store(0, Ordering::Release);
+        }
+        let vec = unsafe { &mut *self.shared.vec.get() };
+        vec.reserve_exact(capacity.saturating_sub(vec.len()));
+        vec
+    }
+
+    // `range` of the visible bytes for writing
+    pub(crate) fn slice_mut(&mut self, range: Range<usize>, capacity: usize) -> &mut [u8] {
+        assert!(range.start <= range.end && range.end <= self.len());
+        let vec = self.vec_mut_from(range.start, capacity);
+        // built from the raw pointer so that nothing below `range` is borrowed
+        unsafe { slice::from_raw_parts_mut(vec.as_mut_ptr().add(range.start), range.len()) }
+    }
+
+    pub(crate) fn append(&mut self, bytes: &[u8], capacity: usize) {
+        let len = self.len();
+        self.vec_mut_from(len, capacity.max(len + bytes.len())).extend_from_slice(bytes);
+    }
+
+    // truncates, or extends with zeros
+    pub(crate) fn resize(&mut self, new_len: usize, capacity: usize) {
+        let len = self.len();
+        if new_len > len {
+            self.vec_mut_from(len, capacity.max(new_len)).resize(new_len, 0);
+        } else if self.owner {
+            unsafe { (*self.shared.vec.get()).truncate(new_len) };
+        } else {
+            self.end = self.start + new_len;
+        }
+    }
+
+    // drops the first `n` bytes; when they are shared only the rest is copied
+    pub(crate) fn discard_front(&mut self, n: usize, capacity: usize) {
+        if self.owner && self.is_unique() {
+            self.vec_mut_from(0, capacity).drain(..n);
+        } else {
+            let mut data = Vec::with_capacity(capacity.max(self.len() - n));
+            data.extend_from_slice(&self[n..]);
+            *self = Storage::new(data);
+        }
+    }
+
+    // the room past the visible bytes, for filling in before `set_len`
+    #[cfg(any(feature = "bytes", feature = "tokio"))]
+    pub(crate) fn spare_mut(&mut self, capacity: usize) -> &mut [MaybeUninit<u8>] {
+        let len = self.len();
+        self.vec_mut_from(len, capacity).spare_capacity_mut()
+    }
+
+    // only right after `spare_mut`, with the bytes up to `len` filled in
+    #[cfg(any(feature = "bytes", feature = "tokio"))]
+    pub(crate) unsafe fn set_len(&mut self, len: usize) {
+        debug_assert!(self.owner);
+        (*self.shared.vec.get()).set_len(len);
+    }
+
+    pub(crate) fn allocated(&self) -> usize {
+        if self.owner { self.vec().capacity() } else { self.end - self.start }
+    }
+
+    // the whole allocation, when nothing else refers to it
+    pub(crate) fn unique_vec(&mut self) -> Option<&mut Vec<u8>> {
+        if !self.owner || !self.is_unique() {
+            return None;
+        }
+        self.shared.frozen.store(0, Ordering::Release);
+        Some(unsafe { &mut *self.shared.vec.get() })
+    }
+
+    #[cfg(feature = "bytes")]
+    pub(crate) fn into_vec(mut self) -> Vec<u8> {
+        if self.owner && self.is_unique() {
+            if let Ok(shared) = Arc::try_unwrap(self.shared) {
+                return shared.vec.into_inner();
+            }
+            unreachable!("checked to be unique");
+        }
+        self.to_vec()
+    }
+}
+
+impl Clone for Storage {
+    // a view of everything this buffer sees
+    fn clone(&self) -> Self {
+        self.slice(0, self.len())
+    }
+}
+
+impl Deref for Storage {
+    type Target = [u8];
+
+    fn deref(&self) -> &[u8] {
+        if self.owner {
+            self.vec()
+        } else {
+            unsafe { slice::from_raw_parts(self.ptr.add(self.start), self.end - self.start) }
+        }
+    }
+}
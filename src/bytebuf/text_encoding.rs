@@ -0,0 +1,154 @@
+use crate::bytebuf::{ByteBuf, ByteReult};
+use crate::error::ByteBufError;
+
+const HEX: &[u8; 16] = b"0123456789abcdef";
+const BASE32: &[u8; 32] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZ234567";
+const BASE64: &[u8; 64] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789+/";
+const BASE64_URL_SAFE: &[u8; 64] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789-_";
+
+/// The base64 flavours of RFC 4648.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Alphabet {
+    /// `+` and `/`, padded with `=`
+    #[default]
+    Standard,
+    /// `+` and `/`, unpadded
+    StandardNoPad,
+    /// `-` and `_`, padded with `=`
+    UrlSafe,
+    /// `-` and `_`, unpadded
+    UrlSafeNoPad,
+}
+
+impl Alphabet {
+    fn table(&self) -> &'static [u8; 64] {
+        match self {
+            Alphabet::Standard | Alphabet::StandardNoPad => BASE64,
+            Alphabet::UrlSafe | Alphabet::UrlSafeNoPad => BASE64_URL_SAFE,
+        }
+    }
+
+    fn padded(&self) -> bool {
+        matches!(self, Alphabet::Standard | Alphabet::UrlSafe)
+    }
+}
+
+// splits `bytes` into `bits` wide digits, padding the text to a multiple of `pad_to` when given
+fn encode(bytes: &[u8], bits: u32, table: &[u8], pad_to: Option<usize>) -> String {
+    let mask = (1u32 << bits) - 1;
+    let mut out = String::with_capacity((bytes.len() * 8).div_ceil(bits as usize));
+    let (mut acc, mut n) = (0u32, 0u32);
+    for &b in bytes {
+        acc = (acc << 8) | b as u32;
+        n += 8;
+        while n >= bits {
+            n -= bits;
+            out.push(table[((acc >> n) & mask) as usize] as char);
+        }
+        acc &= (1 << n) - 1;
+    }
+    if n > 0 {
+        out.push(table[((acc << (bits - n)) & mask) as usize] as char);
+    }
+    if let Some(pad_to) = pad_to {
+        while !out.len().is_multiple_of(pad_to) {
+            out.push('=');
+        }
+    }
+    out
+}
+
+// the inverse of `encode`; padding is optional, but when present it must be exactly
+// what pads the last digits out to a multiple of `pad_to`
+fn decode(text: &str, bits: u32, digit: impl Fn(u8) -> Option<u8>, pad_to: Option<usize>,
+          encoding: &'static str) -> ByteReult<Vec<u8>> {
+    let invalid = |offset| ByteBufError::InvalidEncoding { encoding, offset };
+    let body = match pad_to {
+        Some(pad_to) => {
+            let body = text.trim_end_matches('=');
+            let padded_len = body.len().next_multiple_of(pad_to);
+            if body.len() != text.len() && text.len() != padded_len {
+                return Err(invalid(text.len().min(padded_len)));
+            }
+            body
+        }
+        None => text,
+    };
+    let mut out = Vec::with_capacity(body.len() * bits as usize / 8);
+    let (mut acc, mut n) = (0u32, 0u32);
+    for (i, c) in body.bytes().enumerate() {
+        acc = (acc << bits) | digit(c).ok_or_else(|| invalid(i))? as u32;
+        n += bits;
+        if n >= 8 {
+            n -= 8;
+            out.push((acc >> n) as u8);
+            acc &= (1 << n) - 1;
+        }
+    }
+    // a trailing digit that does not complete a byte
+    if n >= bits {
+        return Err(invalid(body.len()));
+    }
+    Ok(out)
+}
+
+fn hex_digit(c: u8) -> Option<u8> {
+    (c as char).to_digit(16).map(|d| d as u8)
+}
+
+fn base32_digit(c: u8) -> Option<u8> {
+    match c.to_ascii_uppercase() {
+        c @ b'A'..=b'Z' => Some(c - b'A'),
+        c @ b'2'..=b'7' => Some(c - b'2' + 26),
+        _ => None,
+    }
+}
+
+impl ByteBuf {
+    /// The readable bytes as lowercase hex.
+    pub fn to_hex(&self) -> String {
+        encode(&self.buf[self.read_index..self.write_index], 4, HEX, None)
+    }
+
+    /// A buffer holding the bytes spelled by `hex`, in either case.
+    pub fn from_hex(hex: &str) -> ByteReult<ByteBuf> {
+        decode(hex, 4, hex_digit, None, "hex").map(ByteBuf::from)
+    }
+
+    pub fn to_base64(&self, alphabet: Alphabet) -> String {
+        let pad_to = if alphabet.padded() { Some(4) } else { None };
+        encode(&self.buf[self.read_index..self.write_index], 6, alphabet.table(), pad_to)
+    }
+
+    /// Decodes `text` in `alphabet`; padding may be left out even for the padded alphabets.
+    pub fn from_base64(text: &str, alphabet: Alphabet) -> ByteReult<ByteBuf> {
+        let table = alphabet.table();
+        let digit = |c: u8| table.iter().position(|&t| t == c).map(|d| d as u8);
+        let pad_to = if alphabet.padded() { Some(4) } else { None };
+        decode(text, 6, digit, pad_to, "base64").map(ByteBuf::from)
+    }
+
+    /// The readable bytes in the padded RFC 4648 base32 alphabet.
+    pub fn to_base32(&self) -> String {
+        encode(&self.buf[self.read_index..self.write_index], 5, BASE32, Some(8))
+    }
+
+    /// Decodes base32 in either case, with or without padding.
+    pub fn from_base32(text: &str) -> ByteReult<ByteBuf> {
+        decode(text, 5, base32_digit, Some(8), "base32").map(ByteBuf::from)
+    }
+
+    /// Decodes `hex` and writes the bytes; nothing is written when it is invalid.
+    pub fn write_hex_str(&mut self, hex: &str) -> ByteReult<usize> {
+        let bytes = decode(hex, 4, hex_digit, None, "hex")?;
+        Ok(self.write_bytes(&bytes)?)
+    }
+
+    /// Reads `len` bytes and returns them as lowercase hex.
+    pub fn read_hex_str(&mut self, len: usize) -> ByteReult<String> {
+        self.check_readable(len)?;
+        let hex = encode(&self.buf[self.read_index..self.read_index + len], 4, HEX, None);
+        self.read_index += len;
+        Ok(hex)
+    }
+}
@@ -0,0 +1,70 @@
+use std::cmp;
+use std::future::poll_fn;
+use std::io;
+use std::pin::Pin;
+use std::task::{Context, Poll};
+
+use tokio::io::{AsyncRead, AsyncWrite, AsyncWriteExt, ReadBuf};
+
+use crate::bytebuf::ByteBuf;
+
+impl ByteBuf {
+    /// Reads at most `max` bytes from `reader` into the writable tail and advances the writer index.
+    ///
+    /// `max` is cut down to what still fits below `max_capacity`; this only fails
+    /// for lack of room when not a single byte fits. Cancel safe: when the future
+    /// is dropped before it completes the buffer is left as it was.
+    pub async fn read_from_async<R>(&mut self, reader: &mut R, max: usize) -> io::Result<usize>
+        where R: AsyncRead + Unpin {
+        self.maybe_discard_read_bytes();
+        self.ensure_writable(cmp::min(max, cmp::max(self.max_writable_bytes(), 1)))?;
+        let max = cmp::min(max, self.writable_bytes());
+        // the bytes land in the spare capacity, so the Vec length only ever changes
+        // once they are there and never across an await
+        let n = poll_fn(|cx| {
+            let mut read_buf = ReadBuf::uninit(&mut self.buf.spare_mut(self.capacity)[..max]);
+            match Pin::new(&mut *reader).poll_read(cx, &mut read_buf) {
+                Poll::Ready(Ok(())) => Poll::Ready(Ok(read_buf.filled().len())),
+                Poll::Ready(Err(e)) => Poll::Ready(Err(e)),
+                Poll::Pending => Poll::Pending,
+            }
+        }).await?;
+        // the first n spare bytes were filled in by the reader
+        unsafe { self.buf.set_len(self.write_index + n) };
+        self.write_index += n;
+        Ok(n)
+    }
+
+    /// Writes every readable byte to `writer` and advances the reader index.
+    pub async fn write_to_async<W>(&mut self, writer: &mut W) -> io::Result<usize>
+        where W: AsyncWrite + Unpin {
+        let n = self.readable_bytes();
+        writer.write_all(&self.buf[self.read_index..self.write_index]).await?;
+        self.read_index += n;
+        Ok(n)
+    }
+}
+
+impl AsyncRead for ByteBuf {
+    fn poll_read(self: Pin<&mut Self>, _cx: &mut Context<'_>, buf: &mut ReadBuf<'_>) -> Poll<io::Result<()>> {
+        let this = self.get_mut();
+        let n = cmp::min(buf.remaining(), this.readable_bytes());
+        buf.put_slice(&this.buf[this.read_index..this.read_index + n]);
+        this.read_index += n;
+        Poll::Ready(Ok(()))
+    }
+}
+
+impl AsyncWrite for ByteBuf {
+    fn poll_write(self: Pin<&mut Self>, _cx: &mut Context<'_>, buf: &[u8]) -> Poll<io::Result<usize>> {
+        Poll::Ready(self.get_mut().write_bytes(buf))
+    }
+
+    fn poll_flush(self: Pin<&mut Self>, _cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        Poll::Ready(Ok(()))
+    }
+
+    fn poll_shutdown(self: Pin<&mut Self>, _cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        Poll::Ready(Ok(()))
+    }
+}
@@ -0,0 +1,62 @@
+use std::str;
+
+use crate::bytebuf::{ByteBuf, ByteReult, LengthPrefix};
+use crate::bytebuf::cursor::Cursor;
+use crate::error::ByteBufError;
+
+// validates `bytes` as UTF-8; `offset` is where they start, for the error
+pub(crate) fn validate_utf8(bytes: &[u8], offset: usize) -> ByteReult<&str> {
+    str::from_utf8(bytes).map_err(|e| ByteBufError::InvalidUtf8 { offset: offset + e.valid_up_to() })
+}
+
+// the readers that reject invalid UTF-8, for any type with a `cursor()` and a `read_index`
+macro_rules! strict_str_readers {
+    () => {
+        pub fn read_string_strict(&mut self, len: usize) -> ByteReult<String> {
+            let s = self.cursor().get_str(0, len)?.into_owned();
+            self.read_index += len;
+            Ok(s)
+        }
+
+        pub fn read_string_with_len_strict(&mut self, prefix: LengthPrefix) -> ByteReult<String> {
+            let cursor = self.cursor();
+            let (len, width) = cursor.get_len(prefix)?;
+            let s = cursor.get_str(width, len)?.into_owned();
+            self.read_index += width.saturating_add(len);
+            Ok(s)
+        }
+
+        pub fn read_string_with_u8_be_len_strict(&mut self) -> ByteReult<String> {
+            self.read_string_with_len_strict(LengthPrefix::U8)
+        }
+
+        pub fn read_string_with_u16_be_len_strict(&mut self) -> ByteReult<String> {
+            self.read_string_with_len_strict(LengthPrefix::U16Be)
+        }
+
+        pub fn read_string_with_u16_le_len_strict(&mut self) -> ByteReult<String> {
+            self.read_string_with_len_strict(LengthPrefix::U16Le)
+        }
+
+        pub fn read_string_with_u32_be_len_strict(&mut self) -> ByteReult<String> {
+            self.read_string_with_len_strict(LengthPrefix::U32Be)
+        }
+
+        pub fn read_string_with_u32_le_len_strict(&mut self) -> ByteReult<String> {
+            self.read_string_with_len_strict(LengthPrefix::U32Le)
+        }
+    };
+}
+
+pub(crate) use strict_str_readers;
+
+impl ByteBuf {
+    /// Borrows the next `len` bytes as a `&str` without copying and advances the reader index.
+    pub fn read_str_ref(&mut self, len: usize) -> ByteReult<&str> {
+        let s = Cursor::new(&self.buf[..], self.read_index, self.write_index).get_str_ref(0, len)?;
+        self.read_index += len;
+        Ok(s)
+    }
+
+    strict_str_readers!();
+}
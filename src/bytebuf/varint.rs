@@ -0,0 +1,157 @@
+use std::io;
+
+use crate::bytebuf::{ByteBuf, ByteReult};
+use crate::error::ByteBufError;
+
+pub const MAX_VARINT_U32_BYTES: usize = 5;
+pub const MAX_VARINT_U64_BYTES: usize = 10;
+
+fn zigzag_encode_32(v: i32) -> u32 {
+    ((v << 1) ^ (v >> 31)) as u32
+}
+
+fn zigzag_encode_64(v: i64) -> u64 {
+    ((v << 1) ^ (v >> 63)) as u64
+}
+
+pub(crate) fn zigzag_decode_32(v: u32) -> i32 {
+    ((v >> 1) as i32) ^ -((v & 1) as i32)
+}
+
+pub(crate) fn zigzag_decode_64(v: u64) -> i64 {
+    ((v >> 1) as i64) ^ -((v & 1) as i64)
+}
+
+// decodes the varint at the start of `bytes`, returning the value and its encoded
+// length; `offset` is where `bytes` start, for the errors
+pub(crate) fn decode_varint(bytes: &[u8], offset: usize, max_bytes: usize, bits: u32) -> ByteReult<(u64, usize)> {
+    let mut v = 0u64;
+    for i in 0..max_bytes {
+        let b = bytes.get(i).copied()
+            .ok_or(ByteBufError::InsufficientBytes { needed: i + 1, available: i })?;
+        let shift = 7 * i as u32;
+        let payload = (b & 0x7f) as u64;
+        // the last permitted byte may only carry the bits left in the type
+        if shift + 7 > bits && payload >> (bits - shift) != 0 {
+            return Err(ByteBufError::VarintOverflow { offset });
+        }
+        v |= payload << shift;
+        if b & 0x80 == 0 {
+            if b == 0 && i > 0 {
+                return Err(ByteBufError::VarintOverlong { offset });
+            }
+            return Ok((v, i + 1));
+        }
+    }
+    Err(ByteBufError::VarintOverflow { offset })
+}
+
+// the varint getters and readers, for any type with a `cursor()` and a `read_index`
+macro_rules! varint_readers {
+    () => {
+        pub fn try_get_varint_u32(&self) -> ByteReult<u32> {
+            let (v, _) = self.cursor().get_varint($crate::bytebuf::varint::MAX_VARINT_U32_BYTES, 32)?;
+            Ok(v as u32)
+        }
+
+        pub fn try_get_varint_u64(&self) -> ByteReult<u64> {
+            let (v, _) = self.cursor().get_varint($crate::bytebuf::varint::MAX_VARINT_U64_BYTES, 64)?;
+            Ok(v)
+        }
+
+        pub fn try_get_varint_i32(&self) -> ByteReult<i32> {
+            Ok($crate::bytebuf::varint::zigzag_decode_32(self.try_get_varint_u32()?))
+        }
+
+        pub fn try_get_varint_i64(&self) -> ByteReult<i64> {
+            Ok($crate::bytebuf::varint::zigzag_decode_64(self.try_get_varint_u64()?))
+        }
+
+        pub fn get_varint_u32(&self) -> u32 {
+            self.try_get_varint_u32().unwrap()
+        }
+
+        pub fn get_varint_u64(&self) -> u64 {
+            self.try_get_varint_u64().unwrap()
+        }
+
+        pub fn get_varint_i32(&self) -> i32 {
+            self.try_get_varint_i32().unwrap()
+        }
+
+        pub fn get_varint_i64(&self) -> i64 {
+            self.try_get_varint_i64().unwrap()
+        }
+
+        pub fn try_read_varint_u32(&mut self) -> ByteReult<u32> {
+            let (v, n) = self.cursor().get_varint($crate::bytebuf::varint::MAX_VARINT_U32_BYTES, 32)?;
+            self.read_index += n;
+            Ok(v as u32)
+        }
+
+        pub fn try_read_varint_u64(&mut self) -> ByteReult<u64> {
+            let (v, n) = self.cursor().get_varint($crate::bytebuf::varint::MAX_VARINT_U64_BYTES, 64)?;
+            self.read_index += n;
+            Ok(v)
+        }
+
+        pub fn try_read_varint_i32(&mut self) -> ByteReult<i32> {
+            Ok($crate::bytebuf::varint::zigzag_decode_32(self.try_read_varint_u32()?))
+        }
+
+        pub fn try_read_varint_i64(&mut self) -> ByteReult<i64> {
+            Ok($crate::bytebuf::varint::zigzag_decode_64(self.try_read_varint_u64()?))
+        }
+
+        pub fn read_varint_u32(&mut self) -> u32 {
+            self.try_read_varint_u32().unwrap()
+        }
+
+        pub fn read_varint_u64(&mut self) -> u64 {
+            self.try_read_varint_u64().unwrap()
+        }
+
+        pub fn read_varint_i32(&mut self) -> i32 {
+            self.try_read_varint_i32().unwrap()
+        }
+
+        pub fn read_varint_i64(&mut self) -> i64 {
+            self.try_read_varint_i64().unwrap()
+        }
+    };
+}
+
+pub(crate) use varint_readers;
+
+impl ByteBuf {
+    // varint write methods
+
+    pub fn write_varint_u64(&mut self, mut v: u64) -> io::Result<usize> {
+        let mut bytes = [0u8; MAX_VARINT_U64_BYTES];
+        let mut n = 0;
+        while v >= 0x80 {
+            bytes[n] = (v as u8) | 0x80;
+            v >>= 7;
+            n += 1;
+        }
+        bytes[n] = v as u8;
+        self.write_bytes(&bytes[..n + 1])
+    }
+
+    pub fn write_varint_u32(&mut self, v: u32) -> io::Result<usize> {
+        self.write_varint_u64(v as u64)
+    }
+
+    pub fn write_varint_i32(&mut self, v: i32) -> io::Result<usize> {
+        self.write_varint_u32(zigzag_encode_32(v))
+    }
+
+    pub fn write_varint_i64(&mut self, v: i64) -> io::Result<usize> {
+        self.write_varint_u64(zigzag_encode_64(v))
+    }
+
+
+    // varint get and read methods
+
+    varint_readers!();
+}
@@ -0,0 +1,120 @@
+use crate::bytebuf::{ByteBuf, ByteReult};
+use crate::codec::FrameDecoder;
+use crate::error::ByteBufError;
+
+// cuts a frame given the first delimiter in the readable bytes, as (frame length, delimiter length);
+// a frame longer than `max` fails once, after which its bytes are dropped up to the next delimiter
+fn cut_frame(buf: &mut ByteBuf, found: Option<(usize, usize)>, max: usize, strip: bool,
+             discarding: &mut bool) -> ByteReult<Option<ByteBuf>> {
+    match found {
+        Some((len, delim_len)) if *discarding => {
+            *discarding = false;
+            buf.skip_index(len + delim_len)?;
+            Ok(None)
+        }
+        Some((len, delim_len)) if len > max => {
+            buf.skip_index(len + delim_len)?;
+            Err(ByteBufError::FrameTooLong { len, max })
+        }
+        Some((len, delim_len)) => {
+            let frame = if strip {
+                let frame = buf.read_slice(len)?;
+                buf.skip_index(delim_len)?;
+                frame
+            } else {
+                buf.read_slice(len + delim_len)?
+            };
+            Ok(Some(frame))
+        }
+        None if *discarding => {
+            buf.skip_index(buf.readable_bytes())?;
+            Ok(None)
+        }
+        None if buf.readable_bytes() > max => {
+            let len = buf.readable_bytes();
+            buf.skip_index(len)?;
+            *discarding = true;
+            Err(ByteBufError::FrameTooLong { len, max })
+        }
+        None => Ok(None),
+    }
+}
+
+/// Splits lines ending in `\n` or `\r\n`.
+///
+/// A line longer than `max_length` fails with `FrameTooLong` as soon as that is
+/// known and is then skipped, so decoding picks up again at the next line.
+#[derive(Debug, Clone)]
+pub struct LineBasedFrameDecoder {
+    pub max_length: usize,
+    /// leave the line ending out of the frame
+    pub strip_delimiter: bool,
+    discarding: bool,
+}
+
+impl LineBasedFrameDecoder {
+    pub fn new(max_length: usize) -> Self {
+        LineBasedFrameDecoder { max_length, strip_delimiter: true, discarding: false }
+    }
+}
+
+impl FrameDecoder for LineBasedFrameDecoder {
+    fn decode_frame(&mut self, buf: &mut ByteBuf) -> ByteReult<Option<ByteBuf>> {
+        loop {
+            let start = buf.get_reader_index();
+            let found = buf.bytes_before(b'\n').map(|i| {
+                if i > 0 && buf[start + i - 1] == b'\r' { (i - 1, 2) } else { (i, 1) }
+            });
+            let was_discarding = self.discarding;
+            let frame = cut_frame(buf, found, self.max_length, self.strip_delimiter, &mut self.discarding)?;
+            // the tail of a skipped line is gone, there may be a whole line after it
+            if frame.is_none() && was_discarding && found.is_some() {
+                continue;
+            }
+            return Ok(frame);
+        }
+    }
+}
+
+/// Splits frames at any of several delimiters, picking the one that gives the shortest frame.
+///
+/// Frames longer than `max_frame_length` are handled as in `LineBasedFrameDecoder`.
+#[derive(Debug, Clone)]
+pub struct DelimiterBasedFrameDecoder {
+    pub max_frame_length: usize,
+    /// leave the delimiter out of the frame
+    pub strip_delimiter: bool,
+    delimiters: Vec<Vec<u8>>,
+    discarding: bool,
+}
+
+impl DelimiterBasedFrameDecoder {
+    /// Panics when there are no delimiters or one of them is empty.
+    pub fn new<I, D>(max_frame_length: usize, delimiters: I) -> Self
+        where I: IntoIterator<Item = D>, D: AsRef<[u8]> {
+        let delimiters: Vec<Vec<u8>> = delimiters.into_iter().map(|d| d.as_ref().to_vec()).collect();
+        assert!(!delimiters.is_empty() && delimiters.iter().all(|d| !d.is_empty()), "delimiters must not be empty");
+        DelimiterBasedFrameDecoder { max_frame_length, strip_delimiter: true, delimiters, discarding: false }
+    }
+
+    pub fn delimiters(&self) -> &[Vec<u8>] {
+        &self.delimiters
+    }
+}
+
+impl FrameDecoder for DelimiterBasedFrameDecoder {
+    fn decode_frame(&mut self, buf: &mut ByteBuf) -> ByteReult<Option<ByteBuf>> {
+        loop {
+            let start = buf.get_reader_index();
+            let found = self.delimiters.iter()
+                .filter_map(|d| buf.index_of_slice(d).map(|i| (i - start, d.len())))
+                .min_by_key(|&(i, _)| i);
+            let was_discarding = self.discarding;
+            let frame = cut_frame(buf, found, self.max_frame_length, self.strip_delimiter, &mut self.discarding)?;
+            if frame.is_none() && was_discarding && found.is_some() {
+                continue;
+            }
+            return Ok(frame);
+        }
+    }
+}
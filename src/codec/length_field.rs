@@ -0,0 +1,197 @@
+use std::convert::TryFrom;
+
+use crate::bytebuf::{ByteBuf, ByteReult, Endian};
+use crate::bytebuf::varint::{self, MAX_VARINT_U64_BYTES};
+use crate::codec::FrameDecoder;
+use crate::error::ByteBufError;
+
+/// Encoding of the length field in front of a frame.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum LengthField {
+    U8,
+    U16,
+    U24,
+    U32,
+    U64,
+    Varint,
+}
+
+impl LengthField {
+    /// The width in bytes, `None` for `Varint`.
+    pub fn width(&self) -> Option<usize> {
+        match self {
+            LengthField::U8 => Some(1),
+            LengthField::U16 => Some(2),
+            LengthField::U24 => Some(3),
+            LengthField::U32 => Some(4),
+            LengthField::U64 => Some(8),
+            LengthField::Varint => None,
+        }
+    }
+
+    pub fn max_len(&self) -> u64 {
+        match self.width() {
+            Some(8) | None => u64::MAX,
+            Some(width) => (1u64 << (8 * width)) - 1,
+        }
+    }
+}
+
+fn varint_len(mut v: u64) -> usize {
+    let mut n = 1;
+    while v >= 0x80 {
+        v >>= 7;
+        n += 1;
+    }
+    n
+}
+
+/// Splits frames that carry their own length, in the manner of Netty's decoder of the same name.
+///
+/// The length field sits `length_field_offset` bytes into the frame. The frame
+/// spans the bytes up to the end of the field, plus the field value, plus
+/// `length_adjustment`. The first `initial_bytes_to_strip` bytes of it are
+/// dropped before it is returned.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct LengthFieldBasedFrameDecoder {
+    pub max_frame_length: usize,
+    pub length_field_offset: usize,
+    pub length_field: LengthField,
+    pub endian: Endian,
+    pub length_adjustment: isize,
+    pub initial_bytes_to_strip: usize,
+}
+
+impl LengthFieldBasedFrameDecoder {
+    /// A big endian decoder with no adjustment that keeps the header in the frame.
+    pub fn new(max_frame_length: usize, length_field_offset: usize, length_field: LengthField) -> Self {
+        LengthFieldBasedFrameDecoder {
+            max_frame_length,
+            length_field_offset,
+            length_field,
+            endian: Endian::Big,
+            length_adjustment: 0,
+            initial_bytes_to_strip: 0,
+        }
+    }
+
+    // the length field value and where the field ends, relative to the reader index
+    fn peek_length(&self, buf: &ByteBuf) -> ByteReult<Option<(u64, usize)>> {
+        let offset = self.length_field_offset;
+        let start = buf.get_reader_index() + offset;
+        match self.length_field.width() {
+            Some(width) => {
+                if buf.readable_bytes() < offset + width {
+                    return Ok(None);
+                }
+                let bytes = &buf[start..start + width];
+                let v = match self.endian {
+                    Endian::Big => bytes.iter().fold(0u64, |v, &b| v << 8 | b as u64),
+                    Endian::Little => bytes.iter().rev().fold(0u64, |v, &b| v << 8 | b as u64),
+                };
+                Ok(Some((v, offset + width)))
+            }
+            None => {
+                if buf.readable_bytes() <= offset {
+                    return Ok(None);
+                }
+                match varint::decode_varint(&buf[start..], start, MAX_VARINT_U64_BYTES, 64) {
+                    Ok((v, width)) => Ok(Some((v, offset + width))),
+                    Err(ByteBufError::InsufficientBytes { .. }) => Ok(None),
+                    Err(e) => Err(e),
+                }
+            }
+        }
+    }
+}
+
+impl FrameDecoder for LengthFieldBasedFrameDecoder {
+    /// Fails without consuming anything when the frame is longer than
+    /// `max_frame_length` or its length makes no sense; the stream can not be
+    /// resynchronised after that and is best dropped.
+    fn decode_frame(&mut self, buf: &mut ByteBuf) -> ByteReult<Option<ByteBuf>> {
+        let (len, field_end) = match self.peek_length(buf)? {
+            Some(v) => v,
+            None => return Ok(None),
+        };
+        let frame_len = len as i128 + self.length_adjustment as i128 + field_end as i128;
+        if frame_len < field_end as i128 || frame_len < self.initial_bytes_to_strip as i128 {
+            return Err(ByteBufError::InvalidFrameLength { offset: buf.get_reader_index() + self.length_field_offset });
+        }
+        let frame_len = usize::try_from(frame_len).unwrap_or(usize::MAX);
+        if frame_len > self.max_frame_length {
+            return Err(ByteBufError::FrameTooLong { len: frame_len, max: self.max_frame_length });
+        }
+        if buf.readable_bytes() < frame_len {
+            return Ok(None);
+        }
+        buf.skip_index(self.initial_bytes_to_strip)?;
+        buf.read_slice(frame_len - self.initial_bytes_to_strip).map(Some)
+    }
+}
+
+/// Writes frames with a length field in front, for `LengthFieldBasedFrameDecoder` to split again.
+///
+/// The field carries the frame length plus `length_adjustment`, plus its own
+/// width when `length_includes_length_field` is set.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct LengthFieldPrepender {
+    pub length_field: LengthField,
+    pub endian: Endian,
+    pub length_adjustment: isize,
+    pub length_includes_length_field: bool,
+}
+
+impl LengthFieldPrepender {
+    /// A big endian prepender writing just the frame length.
+    pub fn new(length_field: LengthField) -> Self {
+        LengthFieldPrepender {
+            length_field,
+            endian: Endian::Big,
+            length_adjustment: 0,
+            length_includes_length_field: false,
+        }
+    }
+
+    /// Writes the length field and then the readable bytes of `frame` to `out`.
+    pub fn encode(&self, frame: &ByteBuf, out: &mut ByteBuf) -> ByteReult<()> {
+        let body = &frame[frame.get_reader_index()..frame.get_writer_index()];
+        let mut len = body.len() as i128 + self.length_adjustment as i128;
+        if self.length_includes_length_field {
+            len += match self.length_field.width() {
+                Some(width) => width as i128,
+                // settles after a step or two, as the value only ever grows by a byte
+                None => {
+                    let mut width = 1;
+                    while varint_len((len + width as i128).max(0) as u64) != width {
+                        width = varint_len((len + width as i128).max(0) as u64);
+                    }
+                    width as i128
+                }
+            };
+        }
+        let max = self.length_field.max_len();
+        if len < 0 || len > max as i128 {
+            return Err(ByteBufError::LengthPrefixOverflow { len: len.max(0) as usize, max: max as usize });
+        }
+        let len = len as u64;
+        match self.length_field.width() {
+            Some(width) => {
+                let bytes = match self.endian {
+                    Endian::Big => len.to_be_bytes(),
+                    Endian::Little => len.to_le_bytes(),
+                };
+                let field = match self.endian {
+                    Endian::Big => &bytes[8 - width..],
+                    Endian::Little => &bytes[..width],
+                };
+                out.write_bytes(field)?;
+            }
+            None => {
+                out.write_varint_u64(len)?;
+            }
+        }
+        out.write_bytes(body)?;
+        Ok(())
+    }
+}
@@ -0,0 +1,32 @@
+use crate::bytebuf::{ByteBuf, ByteReult};
+
+pub use self::delimiter::{DelimiterBasedFrameDecoder, LineBasedFrameDecoder};
+pub use self::length_field::{LengthField, LengthFieldBasedFrameDecoder, LengthFieldPrepender};
+
+pub mod delimiter;
+pub mod length_field;
+
+#[cfg(feature = "derive")]
+pub use bytebuf_derive::{Decode, Encode};
+
+/// How many elements a decoded sequence may claim beyond the bytes left to read.
+///
+/// Only zero-width elements such as `()` fit there, so a larger count from the
+/// wire is rejected instead of being spun through.
+pub const MAX_ZERO_WIDTH_LEN: usize = 1 << 16;
+
+/// A type that writes itself field by field into a `ByteBuf`, usually via `#[derive(Encode)]`.
+pub trait Encode {
+    fn encode(&self, buf: &mut ByteBuf) -> ByteReult<()>;
+}
+
+/// A type that reads itself back from a `ByteBuf`, usually via `#[derive(Decode)]`.
+pub trait Decode: Sized {
+    fn decode(buf: &mut ByteBuf) -> ByteReult<Self>;
+}
+
+/// Cuts complete frames off the front of an accumulating `ByteBuf`.
+pub trait FrameDecoder {
+    /// The next frame, or `None` until enough bytes have arrived, in which case nothing is consumed.
+    fn decode_frame(&mut self, buf: &mut ByteBuf) -> ByteReult<Option<ByteBuf>>;
+}
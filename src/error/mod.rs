@@ -1,44 +1,106 @@
 use std::error::Error;
 use std::fmt;
+use std::io;
 
-#[derive(Default, Debug, Clone, PartialEq)]
-pub struct ByteBufError {
-    pub message: String,
+#[derive(Debug, Clone, PartialEq)]
+pub enum ByteBufError {
+    /// `len` bytes at `index` do not fit below `bound`
+    IndexOutOfBounds { index: usize, len: usize, bound: usize },
+    /// fewer than `needed` bytes are readable
+    InsufficientBytes { needed: usize, available: usize },
+    /// a length does not fit in the prefix chosen to carry it
+    LengthPrefixOverflow { len: usize, max: usize },
+    /// the varint at `offset` runs past its type's width or maximum byte count
+    VarintOverflow { offset: usize },
+    /// the varint at `offset` is padded with redundant continuation bytes
+    VarintOverlong { offset: usize },
+    /// the bytes at `offset` are not valid UTF-8
+    InvalidUtf8 { offset: usize },
+    /// the buffer can not grow to `required` bytes
+    CapacityExceeded { required: usize, max: usize },
+    /// the `encoding` text (hex, base32, base64) is malformed at `offset`
+    InvalidEncoding { encoding: &'static str, offset: usize },
+    /// a frame of `len` bytes is longer than the decoder allows
+    FrameTooLong { len: usize, max: usize },
+    /// the length field at `offset` describes a frame shorter than its own header
+    InvalidFrameLength { offset: usize },
+    /// a checksum trailer does not match the data it covers
+    ChecksumMismatch { expected: u32, actual: u32 },
+    Io { kind: io::ErrorKind, message: String },
+    Custom(String),
 }
 
 impl ByteBufError {
     pub fn new(message: String) -> Self {
-        ByteBufError {
-            message
-        }
+        ByteBufError::Custom(message)
     }
 
+    #[allow(non_snake_case)]
     pub fn fromIoError(err: std::io::Error) -> Self {
-        ByteBufError {
-            message: err.to_string()
-        }
+        ByteBufError::from(err)
     }
 }
 
 
 impl fmt::Display for ByteBufError {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
-        write!(f, "{}", self.message)
+        match self {
+            ByteBufError::IndexOutOfBounds { index, len, bound } => {
+                write!(f, "index out of bounds (index {}, len {}, bound {})", index, len, bound)
+            }
+            ByteBufError::InsufficientBytes { needed, available } => {
+                write!(f, "insufficient bytes (needed {}, had {})", needed, available)
+            }
+            ByteBufError::LengthPrefixOverflow { len, max } => {
+                write!(f, "length prefix overflow (len {}, max {})", len, max)
+            }
+            ByteBufError::VarintOverflow { offset } => {
+                write!(f, "varint overflow at offset {}", offset)
+            }
+            ByteBufError::VarintOverlong { offset } => {
+                write!(f, "overlong varint at offset {}", offset)
+            }
+            ByteBufError::InvalidUtf8 { offset } => {
+                write!(f, "invalid utf-8 at offset {}", offset)
+            }
+            ByteBufError::CapacityExceeded { required, max } => {
+                write!(f, "capacity exceeded (required {}, max {})", required, max)
+            }
+            ByteBufError::InvalidEncoding { encoding, offset } => {
+                write!(f, "invalid {} at offset {}", encoding, offset)
+            }
+            ByteBufError::FrameTooLong { len, max } => {
+                write!(f, "frame too long (len {}, max {})", len, max)
+            }
+            ByteBufError::InvalidFrameLength { offset } => {
+                write!(f, "invalid frame length at offset {}", offset)
+            }
+            ByteBufError::ChecksumMismatch { expected, actual } => {
+                write!(f, "checksum mismatch (expected {:#010x}, got {:#010x})", expected, actual)
+            }
+            ByteBufError::Io { message, .. } => write!(f, "{}", message),
+            ByteBufError::Custom(message) => write!(f, "{}", message),
+        }
     }
 }
 
-impl Error for ByteBufError {
-    fn description(&self) -> &str {
-        &self.message
-    }
-}
+impl Error for ByteBufError {}
 
 
 impl From<std::io::Error> for ByteBufError {
     fn from(error: std::io::Error) -> Self {
-        ByteBufError{
+        ByteBufError::Io {
+            kind: error.kind(),
             message: error.to_string(),
         }
     }
 }
 
+impl From<ByteBufError> for io::Error {
+    fn from(error: ByteBufError) -> Self {
+        match error {
+            ByteBufError::Io { kind, message } => io::Error::new(kind, message),
+            error => io::Error::other(error),
+        }
+    }
+}
@@ -1,7 +1,13 @@
 
+// lets the derive macros refer to ::bytebuf_rs from inside this crate too
+extern crate self as bytebuf_rs;
+
 pub mod bytebuf;
+pub mod codec;
 pub mod error;
 pub mod testmode;
+#[cfg(feature = "serde")]
+pub mod serde_codec;
 
 
 
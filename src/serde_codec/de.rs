@@ -0,0 +1,353 @@
+use serde::de::{self, DeserializeSeed, IntoDeserializer, Visitor};
+
+use crate::bytebuf::{ByteBuf, ByteReult};
+use crate::error::ByteBufError;
+use crate::serde_codec::{Config, IntEncoding};
+
+pub struct Deserializer<'a> {
+    buf: &'a mut ByteBuf,
+    config: Config,
+}
+
+impl<'a> Deserializer<'a> {
+    pub fn new(buf: &'a mut ByteBuf, config: Config) -> Self {
+        Deserializer { buf, config }
+    }
+
+    fn read_u16(&mut self) -> ByteReult<u16> {
+        match self.config.int_encoding {
+            IntEncoding::Varint => {
+                let v = self.buf.try_get_varint_u32()?;
+                if v > u16::MAX as u32 {
+                    return Err(ByteBufError::VarintOverflow { offset: self.buf.get_reader_index() });
+                }
+                self.buf.try_read_varint_u32()?;
+                Ok(v as u16)
+            }
+            IntEncoding::Fixed => self.buf.try_read_with(self.config.endian),
+        }
+    }
+
+    fn read_i16(&mut self) -> ByteReult<i16> {
+        match self.config.int_encoding {
+            IntEncoding::Varint => {
+                let v = self.buf.try_get_varint_i32()?;
+                if v < i16::MIN as i32 || v > i16::MAX as i32 {
+                    return Err(ByteBufError::VarintOverflow { offset: self.buf.get_reader_index() });
+                }
+                self.buf.try_read_varint_i32()?;
+                Ok(v as i16)
+            }
+            IntEncoding::Fixed => self.buf.try_read_with(self.config.endian),
+        }
+    }
+
+    fn read_u32(&mut self) -> ByteReult<u32> {
+        match self.config.int_encoding {
+            IntEncoding::Varint => self.buf.try_read_varint_u32(),
+            IntEncoding::Fixed => self.buf.try_read_with(self.config.endian),
+        }
+    }
+
+    fn read_i32(&mut self) -> ByteReult<i32> {
+        match self.config.int_encoding {
+            IntEncoding::Varint => self.buf.try_read_varint_i32(),
+            IntEncoding::Fixed => self.buf.try_read_with(self.config.endian),
+        }
+    }
+
+    fn read_u64(&mut self) -> ByteReult<u64> {
+        match self.config.int_encoding {
+            IntEncoding::Varint => self.buf.try_read_varint_u64(),
+            IntEncoding::Fixed => self.buf.try_read_with(self.config.endian),
+        }
+    }
+
+    fn read_i64(&mut self) -> ByteReult<i64> {
+        match self.config.int_encoding {
+            IntEncoding::Varint => self.buf.try_read_varint_i64(),
+            IntEncoding::Fixed => self.buf.try_read_with(self.config.endian),
+        }
+    }
+
+    fn read_tag(&mut self, what: &str) -> ByteReult<bool> {
+        match self.buf.try_read_u8()? {
+            0 => Ok(false),
+            1 => Ok(true),
+            v => Err(ByteBufError::Custom(format!("invalid {} tag {}", what, v))),
+        }
+    }
+
+    fn read_len(&mut self) -> ByteReult<usize> {
+        self.buf.read_len(self.config.length_prefix)
+    }
+
+    // an element count; elements that take up bytes run out of them soon enough,
+    // so only the count beyond the readable bytes is limited
+    fn read_count(&mut self) -> ByteReult<usize> {
+        let offset = self.buf.get_reader_index();
+        let len = self.read_len()?;
+        let max = self.buf.readable_bytes().max(self.config.max_len);
+        if len > max {
+            self.buf.set_reader_index(offset);
+            return Err(ByteBufError::LengthPrefixOverflow { len, max });
+        }
+        Ok(len)
+    }
+}
+
+impl<'de, 'a, 'b> de::Deserializer<'de> for &'a mut Deserializer<'b> {
+    type Error = ByteBufError;
+
+    fn deserialize_any<V>(self, _visitor: V) -> ByteReult<V::Value>
+        where V: Visitor<'de> {
+        Err(ByteBufError::Custom("the ByteBuf format is not self-describing".to_string()))
+    }
+
+    fn deserialize_bool<V>(self, visitor: V) -> ByteReult<V::Value>
+        where V: Visitor<'de> {
+        visitor.visit_bool(self.read_tag("bool")?)
+    }
+
+    fn deserialize_i8<V>(self, visitor: V) -> ByteReult<V::Value>
+        where V: Visitor<'de> {
+        visitor.visit_i8(self.buf.try_read_i8()?)
+    }
+
+    fn deserialize_i16<V>(self, visitor: V) -> ByteReult<V::Value>
+        where V: Visitor<'de> {
+        visitor.visit_i16(self.read_i16()?)
+    }
+
+    fn deserialize_i32<V>(self, visitor: V) -> ByteReult<V::Value>
+        where V: Visitor<'de> {
+        visitor.visit_i32(self.read_i32()?)
+    }
+
+    fn deserialize_i64<V>(self, visitor: V) -> ByteReult<V::Value>
+        where V: Visitor<'de> {
+        visitor.visit_i64(self.read_i64()?)
+    }
+
+    fn deserialize_i128<V>(self, visitor: V) -> ByteReult<V::Value>
+        where V: Visitor<'de> {
+        let v = self.buf.try_read_with(self.config.endian)?;
+        visitor.visit_i128(v)
+    }
+
+    fn deserialize_u8<V>(self, visitor: V) -> ByteReult<V::Value>
+        where V: Visitor<'de> {
+        visitor.visit_u8(self.buf.try_read_u8()?)
+    }
+
+    fn deserialize_u16<V>(self, visitor: V) -> ByteReult<V::Value>
+        where V: Visitor<'de> {
+        visitor.visit_u16(self.read_u16()?)
+    }
+
+    fn deserialize_u32<V>(self, visitor: V) -> ByteReult<V::Value>
+        where V: Visitor<'de> {
+        visitor.visit_u32(self.read_u32()?)
+    }
+
+    fn deserialize_u64<V>(self, visitor: V) -> ByteReult<V::Value>
+        where V: Visitor<'de> {
+        visitor.visit_u64(self.read_u64()?)
+    }
+
+    fn deserialize_u128<V>(self, visitor: V) -> ByteReult<V::Value>
+        where V: Visitor<'de> {
+        let v = self.buf.try_read_with(self.config.endian)?;
+        visitor.visit_u128(v)
+    }
+
+    fn deserialize_f32<V>(self, visitor: V) -> ByteReult<V::Value>
+        where V: Visitor<'de> {
+        let v = self.buf.try_read_with(self.config.endian)?;
+        visitor.visit_f32(v)
+    }
+
+    fn deserialize_f64<V>(self, visitor: V) -> ByteReult<V::Value>
+        where V: Visitor<'de> {
+        let v = self.buf.try_read_with(self.config.endian)?;
+        visitor.visit_f64(v)
+    }
+
+    fn deserialize_char<V>(self, visitor: V) -> ByteReult<V::Value>
+        where V: Visitor<'de> {
+        let v = self.read_u32()?;
+        let c = std::char::from_u32(v)
+            .ok_or_else(|| ByteBufError::Custom(format!("invalid char {:#x}", v)))?;
+        visitor.visit_char(c)
+    }
+
+    fn deserialize_str<V>(self, visitor: V) -> ByteReult<V::Value>
+        where V: Visitor<'de> {
+        self.deserialize_string(visitor)
+    }
+
+    fn deserialize_string<V>(self, visitor: V) -> ByteReult<V::Value>
+        where V: Visitor<'de> {
+        visitor.visit_string(self.buf.read_string_with_len_strict(self.config.length_prefix)?)
+    }
+
+    fn deserialize_bytes<V>(self, visitor: V) -> ByteReult<V::Value>
+        where V: Visitor<'de> {
+        self.deserialize_byte_buf(visitor)
+    }
+
+    fn deserialize_byte_buf<V>(self, visitor: V) -> ByteReult<V::Value>
+        where V: Visitor<'de> {
+        visitor.visit_byte_buf(self.buf.read_bytes_with_len(self.config.length_prefix)?)
+    }
+
+    fn deserialize_option<V>(self, visitor: V) -> ByteReult<V::Value>
+        where V: Visitor<'de> {
+        if self.read_tag("option")? {
+            visitor.visit_some(self)
+        } else {
+            visitor.visit_none()
+        }
+    }
+
+    fn deserialize_unit<V>(self, visitor: V) -> ByteReult<V::Value>
+        where V: Visitor<'de> {
+        visitor.visit_unit()
+    }
+
+    fn deserialize_unit_struct<V>(self, _name: &'static str, visitor: V) -> ByteReult<V::Value>
+        where V: Visitor<'de> {
+        visitor.visit_unit()
+    }
+
+    fn deserialize_newtype_struct<V>(self, _name: &'static str, visitor: V) -> ByteReult<V::Value>
+        where V: Visitor<'de> {
+        visitor.visit_newtype_struct(self)
+    }
+
+    fn deserialize_seq<V>(self, visitor: V) -> ByteReult<V::Value>
+        where V: Visitor<'de> {
+        let len = self.read_count()?;
+        visitor.visit_seq(Access { de: self, remaining: len })
+    }
+
+    fn deserialize_tuple<V>(self, len: usize, visitor: V) -> ByteReult<V::Value>
+        where V: Visitor<'de> {
+        visitor.visit_seq(Access { de: self, remaining: len })
+    }
+
+    fn deserialize_tuple_struct<V>(self, _name: &'static str, len: usize, visitor: V) -> ByteReult<V::Value>
+        where V: Visitor<'de> {
+        visitor.visit_seq(Access { de: self, remaining: len })
+    }
+
+    fn deserialize_map<V>(self, visitor: V) -> ByteReult<V::Value>
+        where V: Visitor<'de> {
+        let len = self.read_count()?;
+        visitor.visit_map(Access { de: self, remaining: len })
+    }
+
+    fn deserialize_struct<V>(self, _name: &'static str, fields: &'static [&'static str], visitor: V) -> ByteReult<V::Value>
+        where V: Visitor<'de> {
+        visitor.visit_seq(Access { de: self, remaining: fields.len() })
+    }
+
+    fn deserialize_enum<V>(self, _name: &'static str, _variants: &'static [&'static str], visitor: V) -> ByteReult<V::Value>
+        where V: Visitor<'de> {
+        visitor.visit_enum(self)
+    }
+
+    fn deserialize_identifier<V>(self, visitor: V) -> ByteReult<V::Value>
+        where V: Visitor<'de> {
+        visitor.visit_u32(self.read_u32()?)
+    }
+
+    fn deserialize_ignored_any<V>(self, _visitor: V) -> ByteReult<V::Value>
+        where V: Visitor<'de> {
+        Err(ByteBufError::Custom("the ByteBuf format can not skip unknown values".to_string()))
+    }
+
+    fn is_human_readable(&self) -> bool {
+        false
+    }
+}
+
+// walks a sequence, map, tuple or struct with a known number of elements
+struct Access<'a, 'b> {
+    de: &'a mut Deserializer<'b>,
+    remaining: usize,
+}
+
+impl<'de, 'a, 'b> de::SeqAccess<'de> for Access<'a, 'b> {
+    type Error = ByteBufError;
+
+    fn next_element_seed<T>(&mut self, seed: T) -> ByteReult<Option<T::Value>>
+        where T: DeserializeSeed<'de> {
+        if self.remaining == 0 {
+            return Ok(None);
+        }
+        self.remaining -= 1;
+        seed.deserialize(&mut *self.de).map(Some)
+    }
+
+    fn size_hint(&self) -> Option<usize> {
+        Some(self.remaining)
+    }
+}
+
+impl<'de, 'a, 'b> de::MapAccess<'de> for Access<'a, 'b> {
+    type Error = ByteBufError;
+
+    fn next_key_seed<K>(&mut self, seed: K) -> ByteReult<Option<K::Value>>
+        where K: DeserializeSeed<'de> {
+        if self.remaining == 0 {
+            return Ok(None);
+        }
+        self.remaining -= 1;
+        seed.deserialize(&mut *self.de).map(Some)
+    }
+
+    fn next_value_seed<V>(&mut self, seed: V) -> ByteReult<V::Value>
+        where V: DeserializeSeed<'de> {
+        seed.deserialize(&mut *self.de)
+    }
+
+    fn size_hint(&self) -> Option<usize> {
+        Some(self.remaining)
+    }
+}
+
+impl<'de, 'a, 'b> de::EnumAccess<'de> for &'a mut Deserializer<'b> {
+    type Error = ByteBufError;
+    type Variant = Self;
+
+    fn variant_seed<V>(self, seed: V) -> ByteReult<(V::Value, Self)>
+        where V: DeserializeSeed<'de> {
+        let index: de::value::U32Deserializer<ByteBufError> = self.read_u32()?.into_deserializer();
+        let v = seed.deserialize(index)?;
+        Ok((v, self))
+    }
+}
+
+impl<'de, 'a, 'b> de::VariantAccess<'de> for &'a mut Deserializer<'b> {
+    type Error = ByteBufError;
+
+    fn unit_variant(self) -> ByteReult<()> {
+        Ok(())
+    }
+
+    fn newtype_variant_seed<T>(self, seed: T) -> ByteReult<T::Value>
+        where T: DeserializeSeed<'de> {
+        seed.deserialize(self)
+    }
+
+    fn tuple_variant<V>(self, len: usize, visitor: V) -> ByteReult<V::Value>
+        where V: Visitor<'de> {
+        de::Deserializer::deserialize_tuple(self, len, visitor)
+    }
+
+    fn struct_variant<V>(self, fields: &'static [&'static str], visitor: V) -> ByteReult<V::Value>
+        where V: Visitor<'de> {
+        de::Deserializer::deserialize_tuple(self, fields.len(), visitor)
+    }
+}
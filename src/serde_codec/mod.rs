@@ -0,0 +1,89 @@
+use std::fmt::Display;
+
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+
+use crate::bytebuf::{ByteBuf, ByteReult, Endian, LengthPrefix};
+use crate::codec::MAX_ZERO_WIDTH_LEN;
+use crate::error::ByteBufError;
+
+pub use self::de::Deserializer;
+pub use self::ser::Serializer;
+
+mod de;
+mod ser;
+
+/// How integers are laid out on the wire.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum IntEncoding {
+    /// the width of the type, in the configured byte order
+    Fixed,
+    /// LEB128 for unsigned and zigzag LEB128 for signed integers; 8 and 128 bit integers stay fixed
+    Varint,
+}
+
+/// Wire layout shared by `Serializer` and `Deserializer`.
+///
+/// Strings, byte arrays, sequences and maps are written with `length_prefix`,
+/// enum variants as a `u32` index and options as a `u8` tag, so a struct
+/// serialized with the defaults matches one written field by field with
+/// `write_u32_be`, `write_string_with_len(LengthPrefix::U32Be, ..)` and so on.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Config {
+    pub endian: Endian,
+    pub int_encoding: IntEncoding,
+    pub length_prefix: LengthPrefix,
+    /// the most elements a sequence or map may claim beyond the bytes left to
+    /// read; only zero-width elements such as `()` fit there, so this keeps a
+    /// huge length from the wire from spinning through them
+    pub max_len: usize,
+}
+
+impl Default for Config {
+    fn default() -> Self {
+        Config {
+            endian: Endian::Big,
+            int_encoding: IntEncoding::Fixed,
+            length_prefix: LengthPrefix::U32Be,
+            max_len: MAX_ZERO_WIDTH_LEN,
+        }
+    }
+}
+
+/// On error the writer index is put back, so no part of the value is left in the buffer.
+pub fn to_bytebuf<T>(value: &T, buf: &mut ByteBuf, config: Config) -> ByteReult<()>
+    where T: Serialize + ?Sized {
+    let start = buf.get_writer_index();
+    // an automatic discard halfway through would move the bytes `start` points at
+    let discard_threshold = buf.discard_threshold();
+    buf.set_discard_threshold(None);
+    let result = value.serialize(&mut Serializer::new(buf, config));
+    if result.is_err() {
+        buf.set_writer_index(start).unwrap();
+    }
+    buf.set_discard_threshold(discard_threshold);
+    result
+}
+
+/// On error the reader index is put back, so a partial value can be retried once more bytes arrive.
+pub fn from_bytebuf<T>(buf: &mut ByteBuf, config: Config) -> ByteReult<T>
+    where T: DeserializeOwned {
+    let start = buf.get_reader_index();
+    let result = T::deserialize(&mut Deserializer::new(buf, config));
+    if result.is_err() {
+        buf.set_reader_index(start);
+    }
+    result
+}
+
+impl serde::ser::Error for ByteBufError {
+    fn custom<T: Display>(msg: T) -> Self {
+        ByteBufError::Custom(msg.to_string())
+    }
+}
+
+impl serde::de::Error for ByteBufError {
+    fn custom<T: Display>(msg: T) -> Self {
+        ByteBufError::Custom(msg.to_string())
+    }
+}
@@ -0,0 +1,327 @@
+use serde::ser::{self, Serialize};
+
+use crate::bytebuf::{ByteBuf, ByteReult};
+use crate::error::ByteBufError;
+use crate::serde_codec::{Config, IntEncoding};
+
+pub struct Serializer<'a> {
+    buf: &'a mut ByteBuf,
+    config: Config,
+}
+
+impl<'a> Serializer<'a> {
+    pub fn new(buf: &'a mut ByteBuf, config: Config) -> Self {
+        Serializer { buf, config }
+    }
+
+    fn write_u16(&mut self, v: u16) -> ByteReult<()> {
+        match self.config.int_encoding {
+            IntEncoding::Varint => self.buf.write_varint_u32(v as u32)?,
+            IntEncoding::Fixed => self.buf.write_with(self.config.endian, v)?,
+        };
+        Ok(())
+    }
+
+    fn write_i16(&mut self, v: i16) -> ByteReult<()> {
+        match self.config.int_encoding {
+            IntEncoding::Varint => self.buf.write_varint_i32(v as i32)?,
+            IntEncoding::Fixed => self.buf.write_with(self.config.endian, v)?,
+        };
+        Ok(())
+    }
+
+    fn write_u32(&mut self, v: u32) -> ByteReult<()> {
+        match self.config.int_encoding {
+            IntEncoding::Varint => self.buf.write_varint_u32(v)?,
+            IntEncoding::Fixed => self.buf.write_with(self.config.endian, v)?,
+        };
+        Ok(())
+    }
+
+    fn write_i32(&mut self, v: i32) -> ByteReult<()> {
+        match self.config.int_encoding {
+            IntEncoding::Varint => self.buf.write_varint_i32(v)?,
+            IntEncoding::Fixed => self.buf.write_with(self.config.endian, v)?,
+        };
+        Ok(())
+    }
+
+    fn write_u64(&mut self, v: u64) -> ByteReult<()> {
+        match self.config.int_encoding {
+            IntEncoding::Varint => self.buf.write_varint_u64(v)?,
+            IntEncoding::Fixed => self.buf.write_with(self.config.endian, v)?,
+        };
+        Ok(())
+    }
+
+    fn write_i64(&mut self, v: i64) -> ByteReult<()> {
+        match self.config.int_encoding {
+            IntEncoding::Varint => self.buf.write_varint_i64(v)?,
+            IntEncoding::Fixed => self.buf.write_with(self.config.endian, v)?,
+        };
+        Ok(())
+    }
+
+    fn write_len(&mut self, len: Option<usize>) -> ByteReult<()> {
+        let len = len.ok_or_else(|| ByteBufError::Custom("sequence length must be known up front".to_string()))?;
+        self.buf.write_len(self.config.length_prefix, len)
+    }
+}
+
+impl<'a, 'b> ser::Serializer for &'a mut Serializer<'b> {
+    type Ok = ();
+    type Error = ByteBufError;
+    type SerializeSeq = Self;
+    type SerializeTuple = Self;
+    type SerializeTupleStruct = Self;
+    type SerializeTupleVariant = Self;
+    type SerializeMap = Self;
+    type SerializeStruct = Self;
+    type SerializeStructVariant = Self;
+
+    fn serialize_bool(self, v: bool) -> ByteReult<()> {
+        self.buf.write_bool(v)?;
+        Ok(())
+    }
+
+    fn serialize_i8(self, v: i8) -> ByteReult<()> {
+        self.buf.write_i8_be(v)?;
+        Ok(())
+    }
+
+    fn serialize_i16(self, v: i16) -> ByteReult<()> {
+        self.write_i16(v)
+    }
+
+    fn serialize_i32(self, v: i32) -> ByteReult<()> {
+        self.write_i32(v)
+    }
+
+    fn serialize_i64(self, v: i64) -> ByteReult<()> {
+        self.write_i64(v)
+    }
+
+    fn serialize_i128(self, v: i128) -> ByteReult<()> {
+        self.buf.write_with(self.config.endian, v)?;
+        Ok(())
+    }
+
+    fn serialize_u8(self, v: u8) -> ByteReult<()> {
+        self.buf.write_u8_be(v)?;
+        Ok(())
+    }
+
+    fn serialize_u16(self, v: u16) -> ByteReult<()> {
+        self.write_u16(v)
+    }
+
+    fn serialize_u32(self, v: u32) -> ByteReult<()> {
+        self.write_u32(v)
+    }
+
+    fn serialize_u64(self, v: u64) -> ByteReult<()> {
+        self.write_u64(v)
+    }
+
+    fn serialize_u128(self, v: u128) -> ByteReult<()> {
+        self.buf.write_with(self.config.endian, v)?;
+        Ok(())
+    }
+
+    fn serialize_f32(self, v: f32) -> ByteReult<()> {
+        self.buf.write_with(self.config.endian, v)?;
+        Ok(())
+    }
+
+    fn serialize_f64(self, v: f64) -> ByteReult<()> {
+        self.buf.write_with(self.config.endian, v)?;
+        Ok(())
+    }
+
+    fn serialize_char(self, v: char) -> ByteReult<()> {
+        self.write_u32(v as u32)
+    }
+
+    fn serialize_str(self, v: &str) -> ByteReult<()> {
+        self.buf.write_string_with_len(self.config.length_prefix, v)?;
+        Ok(())
+    }
+
+    fn serialize_bytes(self, v: &[u8]) -> ByteReult<()> {
+        self.buf.write_bytes_with_len(self.config.length_prefix, v)?;
+        Ok(())
+    }
+
+    fn serialize_none(self) -> ByteReult<()> {
+        self.buf.write_u8_be(0)?;
+        Ok(())
+    }
+
+    fn serialize_some<T>(self, value: &T) -> ByteReult<()>
+        where T: Serialize + ?Sized {
+        self.buf.write_u8_be(1)?;
+        value.serialize(self)
+    }
+
+    fn serialize_unit(self) -> ByteReult<()> {
+        Ok(())
+    }
+
+    fn serialize_unit_struct(self, _name: &'static str) -> ByteReult<()> {
+        Ok(())
+    }
+
+    fn serialize_unit_variant(self, _name: &'static str, variant_index: u32, _variant: &'static str) -> ByteReult<()> {
+        self.write_u32(variant_index)
+    }
+
+    fn serialize_newtype_struct<T>(self, _name: &'static str, value: &T) -> ByteReult<()>
+        where T: Serialize + ?Sized {
+        value.serialize(self)
+    }
+
+    fn serialize_newtype_variant<T>(self, _name: &'static str, variant_index: u32, _variant: &'static str, value: &T) -> ByteReult<()>
+        where T: Serialize + ?Sized {
+        self.write_u32(variant_index)?;
+        value.serialize(self)
+    }
+
+    fn serialize_seq(self, len: Option<usize>) -> ByteReult<Self> {
+        self.write_len(len)?;
+        Ok(self)
+    }
+
+    fn serialize_tuple(self, _len: usize) -> ByteReult<Self> {
+        Ok(self)
+    }
+
+    fn serialize_tuple_struct(self, _name: &'static str, _len: usize) -> ByteReult<Self> {
+        Ok(self)
+    }
+
+    fn serialize_tuple_variant(self, _name: &'static str, variant_index: u32, _variant: &'static str, _len: usize) -> ByteReult<Self> {
+        self.write_u32(variant_index)?;
+        Ok(self)
+    }
+
+    fn serialize_map(self, len: Option<usize>) -> ByteReult<Self> {
+        self.write_len(len)?;
+        Ok(self)
+    }
+
+    fn serialize_struct(self, _name: &'static str, _len: usize) -> ByteReult<Self> {
+        Ok(self)
+    }
+
+    fn serialize_struct_variant(self, _name: &'static str, variant_index: u32, _variant: &'static str, _len: usize) -> ByteReult<Self> {
+        self.write_u32(variant_index)?;
+        Ok(self)
+    }
+
+    fn is_human_readable(&self) -> bool {
+        false
+    }
+}
+
+impl<'a, 'b> ser::SerializeSeq for &'a mut Serializer<'b> {
+    type Ok = ();
+    type Error = ByteBufError;
+
+    fn serialize_element<T>(&mut self, value: &T) -> ByteReult<()>
+        where T: Serialize + ?Sized {
+        value.serialize(&mut **self)
+    }
+
+    fn end(self) -> ByteReult<()> {
+        Ok(())
+    }
+}
+
+impl<'a, 'b> ser::SerializeTuple for &'a mut Serializer<'b> {
+    type Ok = ();
+    type Error = ByteBufError;
+
+    fn serialize_element<T>(&mut self, value: &T) -> ByteReult<()>
+        where T: Serialize + ?Sized {
+        value.serialize(&mut **self)
+    }
+
+    fn end(self) -> ByteReult<()> {
+        Ok(())
+    }
+}
+
+impl<'a, 'b> ser::SerializeTupleStruct for &'a mut Serializer<'b> {
+    type Ok = ();
+    type Error = ByteBufError;
+
+    fn serialize_field<T>(&mut self, value: &T) -> ByteReult<()>
+        where T: Serialize + ?Sized {
+        value.serialize(&mut **self)
+    }
+
+    fn end(self) -> ByteReult<()> {
+        Ok(())
+    }
+}
+
+impl<'a, 'b> ser::SerializeTupleVariant for &'a mut Serializer<'b> {
+    type Ok = ();
+    type Error = ByteBufError;
+
+    fn serialize_field<T>(&mut self, value: &T) -> ByteReult<()>
+        where T: Serialize + ?Sized {
+        value.serialize(&mut **self)
+    }
+
+    fn end(self) -> ByteReult<()> {
+        Ok(())
+    }
+}
+
+impl<'a, 'b> ser::SerializeMap for &'a mut Serializer<'b> {
+    type Ok = ();
+    type Error = ByteBufError;
+
+    fn serialize_key<T>(&mut self, key: &T) -> ByteReult<()>
+        where T: Serialize + ?Sized {
+        key.serialize(&mut **self)
+    }
+
+    fn serialize_value<T>(&mut self, value: &T) -> ByteReult<()>
+        where T: Serialize + ?Sized {
+        value.serialize(&mut **self)
+    }
+
+    fn end(self) -> ByteReult<()> {
+        Ok(())
+    }
+}
+
+impl<'a, 'b> ser::SerializeStruct for &'a mut Serializer<'b> {
+    type Ok = ();
+    type Error = ByteBufError;
+
+    fn serialize_field<T>(&mut self, _key: &'static str, value: &T) -> ByteReult<()>
+        where T: Serialize + ?Sized {
+        value.serialize(&mut **self)
+    }
+
+    fn end(self) -> ByteReult<()> {
+        Ok(())
+    }
+}
+
+impl<'a, 'b> ser::SerializeStructVariant for &'a mut Serializer<'b> {
+    type Ok = ();
+    type Error = ByteBufError;
+
+    fn serialize_field<T>(&mut self, _key: &'static str, value: &T) -> ByteReult<()>
+        where T: Serialize + ?Sized {
+        value.serialize(&mut **self)
+    }
+
+    fn end(self) -> ByteReult<()> {
+        Ok(())
+    }
+}
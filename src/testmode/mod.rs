@@ -1,10 +1,12 @@
 #[cfg(test)]
 pub mod test {
-    use std::ops::{Deref, DerefMut};
+    use std::io::{BufRead, Read, Seek, SeekFrom, Write};
 
-    use crate::bytebuf::ByteBuf;
+    use crate::bytebuf::{ByteBuf, CompositeByteBuf, LengthPrefix};
+    use crate::error::ByteBufError;
 
     #[test]
+    #[allow(unused_must_use, unused_mut, clippy::needless_as_bytes)]
     fn test_bytebuf() {
         let mut buf = ByteBuf::new_with_capacity(0);
         println!("bytebuf capacity :{}", buf.capacity());
@@ -52,4 +54,1209 @@ pub mod test {
         let buf_copy = buf.deep_clone();
         println!("buf_copy redadindex :{}", buf_copy.get_reader_index());
     }
-}
\ No newline at end of file
+
+    #[test]
+    fn test_get_f64_le() {
+        let mut buf = ByteBuf::new_with_capacity(0);
+        buf.write_f64_le(89.001f64).unwrap();
+        buf.write_f64_be(89.001f64).unwrap();
+        assert_eq!(buf.get_f64_le(), 89.001f64);
+        assert_eq!(buf.read_f64_le(), 89.001f64);
+        assert_eq!(buf.get_f64_be(), 89.001f64);
+        assert_ne!(buf.get_f64_le(), 89.001f64);
+    }
+
+    #[test]
+    fn test_try_read_short_buffer() {
+        let mut buf = ByteBuf::new_with_capacity(0);
+        buf.write_u16_be(7u16).unwrap();
+        buf.write_u8_be(b'h').unwrap();
+
+        assert_eq!(buf.try_get_u16_be().unwrap(), 7u16);
+        assert!(buf.try_read_u32_be().is_err());
+        assert_eq!(buf.get_reader_index(), 0);
+
+        // length prefix says 7 but only 1 byte follows, nothing is consumed
+        let err = buf.try_read_string_with_u16_be_len().unwrap_err();
+        assert_eq!(err, ByteBufError::InsufficientBytes { needed: 9, available: 3 });
+        assert_eq!(buf.get_reader_index(), 0);
+
+        buf.write_str("ello w").unwrap();
+        assert_eq!(buf.try_read_string_with_u16_be_len().unwrap(), "hello w");
+        assert!(buf.try_read_u8().is_err());
+
+        let mut bytes = [0u8; 1];
+        assert!(buf.try_read_bytes(&mut bytes).is_err());
+        buf.write_f64_le(1.5f64).unwrap();
+        assert_eq!(buf.try_read_f64_le().unwrap(), 1.5f64);
+    }
+
+    #[test]
+    fn test_error_kinds() {
+        let mut buf = ByteBuf::new_with_capacity(8);
+        assert_eq!(buf.set_u32_be(6, 1u32).unwrap_err(),
+                   ByteBufError::IndexOutOfBounds { index: 6, len: 4, bound: 0 });
+
+        let long = "x".repeat(300);
+        assert_eq!(buf.write_string_with_u8_be_len(long).unwrap_err(),
+                   ByteBufError::LengthPrefixOverflow { len: 300, max: 255 });
+
+        buf.write_u16_le(1u16).unwrap();
+        assert_eq!(buf.skip_index(3).unwrap_err(),
+                   ByteBufError::InsufficientBytes { needed: 3, available: 2 });
+
+        let err = ByteBufError::from(std::io::Error::new(std::io::ErrorKind::UnexpectedEof, "eof"));
+        assert!(matches!(err, ByteBufError::Io { kind: std::io::ErrorKind::UnexpectedEof, .. }));
+    }
+
+    #[test]
+    fn test_std_io() {
+        let mut buf = ByteBuf::new_with_capacity(0);
+        write!(buf, "hello {}", 42).unwrap();
+        buf.write_all(b"\nworld").unwrap();
+        assert_eq!(buf.get_writer_index(), 14);
+
+        buf.mark_reader_index();
+        let mut line = String::new();
+        buf.read_line(&mut line).unwrap();
+        assert_eq!(line, "hello 42\n");
+        buf.reset_reader_index();
+        assert_eq!(buf.get_reader_index(), 0);
+
+        buf.seek(SeekFrom::Current(6)).unwrap();
+        buf.mark_reader_index();
+        assert_eq!(buf.seek(SeekFrom::End(-5)).unwrap(), 9);
+        let mut rest = Vec::new();
+        buf.read_to_end(&mut rest).unwrap();
+        assert_eq!(rest, b"world");
+        buf.reset_reader_index();
+        assert_eq!(buf.read_u8(), b'4');
+        assert!(buf.seek(SeekFrom::Start(15)).is_err());
+
+        let mut out = ByteBuf::new_with_capacity(0);
+        buf.set_reader_index(0);
+        std::io::copy(&mut buf, &mut out).unwrap();
+        assert_eq!(out.as_slice(), b"hello 42\nworld");
+    }
+
+    #[test]
+    fn test_varint() {
+        let mut buf = ByteBuf::new_with_capacity(0);
+        buf.write_varint_u32(300u32).unwrap();
+        buf.write_varint_i32(-1i32).unwrap();
+        buf.write_varint_i64(i64::MIN).unwrap();
+        buf.write_varint_u64(u64::MAX).unwrap();
+        assert_eq!(&buf.as_slice()[..3], &[0xac, 0x02, 0x01]);
+
+        assert_eq!(buf.get_varint_u32(), 300u32);
+        assert_eq!(buf.read_varint_u32(), 300u32);
+        assert_eq!(buf.read_varint_i32(), -1i32);
+        assert_eq!(buf.try_read_varint_i64().unwrap(), i64::MIN);
+        assert_eq!(buf.try_read_varint_u64().unwrap(), u64::MAX);
+
+        let mut buf = ByteBuf::new_from(&[0x80, 0x80]);
+        assert_eq!(buf.try_read_varint_u32().unwrap_err(),
+                   ByteBufError::InsufficientBytes { needed: 3, available: 2 });
+        assert_eq!(buf.get_reader_index(), 0);
+
+        let buf = ByteBuf::new_from(&[0x81, 0x00]);
+        assert_eq!(buf.try_get_varint_u64().unwrap_err(), ByteBufError::VarintOverlong { offset: 0 });
+
+        let buf = ByteBuf::new_from(&[0xff, 0xff, 0xff, 0xff, 0x1f]);
+        assert_eq!(buf.try_get_varint_u32().unwrap_err(), ByteBufError::VarintOverflow { offset: 0 });
+        assert_eq!(buf.try_get_varint_u64().unwrap(), 0x1_ffff_ffffu64);
+
+        let buf = ByteBuf::new_from(&[0x80; 11]);
+        assert_eq!(buf.try_get_varint_u64().unwrap_err(), ByteBufError::VarintOverflow { offset: 0 });
+    }
+
+    #[test]
+    fn test_length_prefix() {
+        let mut buf = ByteBuf::new_with_capacity(0);
+        buf.write_string_with_len(LengthPrefix::Varint, "hello").unwrap();
+        buf.write_bytes_with_len(LengthPrefix::U64Le, &[1, 2, 3]).unwrap();
+        buf.write_string_with_u32_be_len("x".repeat(70000)).unwrap();
+        assert_eq!(&buf.as_slice()[..6], b"\x05hello");
+
+        assert_eq!(buf.read_string_with_len(LengthPrefix::Varint).unwrap(), "hello");
+        assert_eq!(buf.read_bytes_with_len(LengthPrefix::U64Le).unwrap(), vec![1, 2, 3]);
+        assert_eq!(buf.read_string_with_len(LengthPrefix::U32Be).unwrap().len(), 70000);
+
+        assert_eq!(buf.write_bytes_with_len(LengthPrefix::U16Be, &[0u8; 70000]).unwrap_err(),
+                   ByteBufError::LengthPrefixOverflow { len: 70000, max: 65535 });
+
+        let mut buf = ByteBuf::new_from(&[0x00, 0x04, b'a']);
+        assert!(buf.read_bytes_with_len(LengthPrefix::U16Be).is_err());
+        assert_eq!(buf.get_reader_index(), 0);
+    }
+
+    #[test]
+    fn test_strict_utf8() {
+        let mut buf = ByteBuf::new_with_capacity(0);
+        buf.write_string_with_u16_be_len("héllo".to_string()).unwrap();
+        buf.write_u8_be(3).unwrap();
+        buf.write_bytes(&[b'a', 0xff, b'b']).unwrap();
+
+        assert_eq!(buf.read_string_with_u16_be_len_strict().unwrap(), "héllo");
+        assert_eq!(buf.read_string_with_u8_be_len_strict().unwrap_err(), ByteBufError::InvalidUtf8 { offset: 10 });
+        assert_eq!(buf.get_reader_index(), 8);
+
+        buf.skip_index(1).unwrap();
+        assert_eq!(buf.read_str_ref(1).unwrap(), "a");
+        assert_eq!(buf.read_string_strict(2).unwrap_err(), ByteBufError::InvalidUtf8 { offset: 10 });
+        assert_eq!(buf.read_string(2), "\u{fffd}b");
+
+        // a length near u64::MAX from the wire fails instead of overflowing
+        for prefix in [LengthPrefix::U64Be, LengthPrefix::Varint] {
+            let mut buf = ByteBuf::new_with_capacity(0);
+            buf.write_len(prefix, usize::MAX).unwrap();
+            buf.write_bytes(b"ab").unwrap();
+            let available = buf.readable_bytes();
+            assert_eq!(buf.read_string_with_len_strict(prefix).unwrap_err(),
+                       ByteBufError::InsufficientBytes { needed: usize::MAX, available });
+            assert_eq!(buf.get_reader_index(), 0);
+        }
+    }
+
+    #[cfg(feature = "bytes")]
+    #[test]
+    fn test_bytes_buf() {
+        use bytes::{Buf, BufMut, Bytes, BytesMut};
+        use crate::bytebuf::Endian;
+
+        let mut buf = ByteBuf::new_with_capacity(0);
+        buf.as_buf().put_u32(0xdeadbeef);
+        buf.as_buf().put_slice(b"abc");
+        buf.as_buf().put_u16_le(7);
+        assert_eq!(buf.get_writer_index(), 9);
+        assert_eq!(buf.read_u32_be(), 0xdeadbeef);
+
+        assert_eq!(buf.as_buf().remaining(), 5);
+        assert_eq!(buf.as_buf().get_u8(), b'a');
+        assert_eq!(buf.get_reader_index(), 5);
+
+        // the inherent get_* peek in the buffer's endian, the Buf ones advance in big endian
+        fn buf_get_u16<B: Buf>(b: &mut B) -> u16 {
+            b.get_u16()
+        }
+        let mut both = ByteBuf::new_from(&[1, 2, 3, 4]);
+        both.set_endian(Endian::Little);
+        assert_eq!(both.get_u16(), 0x0201);
+        assert_eq!(both.get_reader_index(), 0);
+        assert_eq!(both.as_buf().get_u16(), 0x0102);
+        assert_eq!(both.get_reader_index(), 2);
+        assert_eq!(buf_get_u16(&mut both.as_buf()), 0x0304);
+        assert_eq!(both.get_reader_index(), 4);
+
+        // a full buffer hands out no room instead of panicking
+        let mut full = ByteBuf::new_with_capacity(4);
+        full.set_max_capacity(4).unwrap();
+        full.write_u32_be(1).unwrap();
+        let mut adapter = full.as_buf();
+        assert_eq!(adapter.remaining_mut(), 0);
+        assert_eq!(adapter.chunk_mut().len(), 0);
+
+        let bytes = Bytes::from(buf);
+        assert_eq!(&bytes[..], b"bc\x07\x00");
+
+        let mut buf = ByteBuf::from(BytesMut::from(&b"xyz"[..]));
+        assert_eq!(buf.read_u8(), b'x');
+        let mut bytes_mut = BytesMut::from(buf);
+        assert_eq!(bytes_mut.get_u16(), u16::from_be_bytes(*b"yz"));
+    }
+
+    #[cfg(feature = "tokio")]
+    #[tokio::test]
+    async fn test_tokio_io() {
+        use tokio::io::{AsyncReadExt, AsyncWriteExt};
+
+        // std::io::{Read, Write} are in scope too, so the tokio calls are spelled out
+        let (mut client, mut server) = tokio::io::duplex(64);
+        let mut out = ByteBuf::new_with_capacity(0);
+        out.write_u32_be(5u32).unwrap();
+        out.write_str("hello").unwrap();
+        assert_eq!(out.write_to_async(&mut client).await.unwrap(), 9);
+        assert!(!out.is_readable());
+
+        let mut inbound = ByteBuf::new_with_capacity(0);
+        assert_eq!(inbound.read_from_async(&mut server, 4).await.unwrap(), 4);
+        assert_eq!(inbound.read_u32_be(), 5u32);
+        assert_eq!(inbound.read_from_async(&mut server, 64).await.unwrap(), 5);
+        assert_eq!(inbound.get_writer_index(), 9);
+        assert_eq!(inbound.read_string(5), "hello");
+
+        // a read dropped while waiting leaves the buffer as it was
+        {
+            use std::future::{poll_fn, Future};
+            use std::task::Poll;
+
+            let mut read = Box::pin(inbound.read_from_async(&mut server, 16));
+            assert!(poll_fn(|cx| Poll::Ready(read.as_mut().poll(cx).is_pending())).await);
+        }
+        assert_eq!(inbound.get_writer_index(), 9);
+        inbound.write_str("more").unwrap();
+        assert_eq!(inbound.read_string(4), "more");
+        assert_eq!(&inbound[..], b"\0\0\0\x05hellomore");
+
+        // the request is cut down to what fits below max_capacity
+        inbound.set_max_capacity(15).unwrap();
+        AsyncWriteExt::write_all(&mut client, b"0123456789").await.unwrap();
+        assert_eq!(inbound.read_from_async(&mut server, 64).await.unwrap(), 2);
+        assert!(inbound.read_from_async(&mut server, 64).await.is_err());
+        assert_eq!(inbound.read_from_async(&mut server, 0).await.unwrap(), 0);
+
+        let mut buf = ByteBuf::new_with_capacity(0);
+        AsyncWriteExt::write_all(&mut buf, b"ping").await.unwrap();
+        let mut s = String::new();
+        AsyncReadExt::read_to_string(&mut buf, &mut s).await.unwrap();
+        assert_eq!(s, "ping");
+    }
+
+    #[test]
+    fn test_discard_read_bytes() {
+        let mut buf = ByteBuf::new_with_capacity(0);
+        buf.write_str("0123456789").unwrap();
+        buf.skip_index(4).unwrap();
+        buf.mark_reader_index();
+        buf.skip_index(2).unwrap();
+
+        buf.discard_some_read_bytes();
+        assert_eq!(buf.get_reader_index(), 0);
+        assert_eq!(buf.get_writer_index(), 4);
+        assert_eq!(buf.as_slice(), b"6789");
+        // the mark pointed into the discarded region
+        buf.reset_reader_index();
+        assert_eq!(buf.get_reader_index(), 0);
+
+        buf.skip_index(1).unwrap();
+        buf.discard_some_read_bytes();
+        assert_eq!(buf.get_reader_index(), 1);
+
+        buf.set_discard_threshold(Some(3));
+        buf.skip_index(2).unwrap();
+        buf.write_u8_be(b'a').unwrap();
+        assert_eq!(buf.as_slice(), b"9a");
+        assert_eq!(buf.read_string(2), "9a");
+
+        // a decoder marks, reads half a header and waits for more bytes
+        let mut buf = ByteBuf::new_with_capacity(0);
+        buf.set_discard_threshold(Some(4));
+        buf.write_str("skip").unwrap();
+        buf.write_u16_be(0xABCD).unwrap();
+        buf.skip_index(4).unwrap();
+        buf.mark_reader_index();
+        assert_eq!(buf.read_u8(), 0xAB);
+        buf.write_u8_be(0xEF).unwrap();
+        assert_eq!(buf.as_slice(), &[0xAB, 0xCD, 0xEF]);
+        assert_eq!(buf.get_reader_index(), 1);
+        buf.reset_reader_index();
+        assert_eq!(buf.get_reader_index(), 0);
+        assert_eq!(buf.read_u8(), 0xAB);
+    }
+
+    #[test]
+    fn test_writer_index_mark() {
+        let mut buf = ByteBuf::new_with_capacity(16);
+        buf.write_u8_be(1).unwrap();
+
+        buf.mark_writer_index();
+        buf.write_string_with_u8_be_len("partial".to_string()).unwrap();
+        buf.reset_writer_index();
+        assert_eq!(buf.get_writer_index(), 1);
+        assert_eq!(buf.as_slice(), &[1]);
+
+        // reserve a length field and fill it in afterwards
+        let len_index = buf.get_writer_index();
+        buf.set_writer_index(len_index + 2).unwrap();
+        buf.write_str("abc").unwrap();
+        buf.set_u16_be(len_index, 3).unwrap();
+        assert_eq!(buf.as_slice(), &[1, 0, 3, b'a', b'b', b'c']);
+
+        buf.skip_index(3).unwrap();
+        assert_eq!(buf.set_writer_index(2).unwrap_err(), ByteBufError::IndexOutOfBounds { index: 2, len: 0, bound: 16 });
+        assert!(buf.set_writer_index(17).is_err());
+        buf.set_writer_index(4).unwrap();
+        assert_eq!(buf.read_string(1), "a");
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn test_serde_codec() {
+        use std::collections::BTreeMap;
+
+        use serde::{Deserialize, Serialize};
+
+        use crate::bytebuf::{Endian, LengthPrefix};
+        use crate::serde_codec::{from_bytebuf, to_bytebuf, Config, IntEncoding};
+
+        #[derive(Debug, PartialEq, Serialize, Deserialize)]
+        enum Kind {
+            Ping,
+            Data(Vec<u8>),
+            Move { x: i16, y: i16 },
+        }
+
+        #[derive(Debug, PartialEq, Serialize, Deserialize)]
+        struct Message {
+            id: u32,
+            name: String,
+            score: f64,
+            tag: Option<char>,
+            kinds: Vec<Kind>,
+            attrs: BTreeMap<String, i64>,
+        }
+
+        let msg = Message {
+            id: 7,
+            name: "hello".to_string(),
+            score: 1.5,
+            tag: Some('z'),
+            kinds: vec![Kind::Ping, Kind::Data(vec![1, 2]), Kind::Move { x: -3, y: 300 }],
+            attrs: vec![("a".to_string(), -1i64)].into_iter().collect(),
+        };
+
+        // the default layout matches the hand written one
+        let mut buf = ByteBuf::new_with_capacity(0);
+        to_bytebuf(&(7u32, "hello"), &mut buf, Config::default()).unwrap();
+        let mut manual = ByteBuf::new_with_capacity(0);
+        manual.write_u32_be(7).unwrap();
+        manual.write_string_with_u32_be_len("hello".to_string()).unwrap();
+        assert_eq!(buf.as_slice(), manual.as_slice());
+
+        let configs = [
+            Config::default(),
+            Config { endian: Endian::Little, int_encoding: IntEncoding::Varint, length_prefix: LengthPrefix::Varint, ..Config::default() },
+        ];
+        for config in configs.iter() {
+            let mut buf = ByteBuf::new_with_capacity(0);
+            to_bytebuf(&msg, &mut buf, *config).unwrap();
+            let decoded: Message = from_bytebuf(&mut buf, *config).unwrap();
+            assert_eq!(decoded, msg);
+            assert!(!buf.is_readable());
+        }
+
+        let mut buf = ByteBuf::new_from(&[0, 0, 0, 9]);
+        assert!(from_bytebuf::<Kind>(&mut buf, Config::default()).is_err());
+
+        // a truncated value is not consumed
+        let mut full = ByteBuf::new_with_capacity(0);
+        to_bytebuf(&msg, &mut full, Config::default()).unwrap();
+        let (head, tail) = full.split_at(full.len() - 3);
+        let mut buf = ByteBuf::new_from(head);
+        assert!(from_bytebuf::<Message>(&mut buf, Config::default()).is_err());
+        assert_eq!(buf.get_reader_index(), 0);
+        buf.write_bytes(tail).unwrap();
+        assert_eq!(from_bytebuf::<Message>(&mut buf, Config::default()).unwrap(), msg);
+
+        // a value that runs out of room is not left half written
+        let mut buf = ByteBuf::new_with_capacity(0);
+        buf.write_u8_be(0xAA).unwrap();
+        buf.set_max_capacity(full.len()).unwrap();
+        assert!(to_bytebuf(&msg, &mut buf, Config::default()).is_err());
+        assert_eq!(buf.as_slice(), &[0xAA]);
+
+        // zero-width elements can outnumber the bytes, but only up to max_len
+        let config = Config { length_prefix: LengthPrefix::U64Be, ..Config::default() };
+        let mut buf = ByteBuf::new_with_capacity(0);
+        to_bytebuf(&vec![(); 3], &mut buf, config).unwrap();
+        assert_eq!(from_bytebuf::<Vec<()>>(&mut buf, config).unwrap().len(), 3);
+        buf.write_u64_be(u64::MAX).unwrap();
+        assert!(matches!(from_bytebuf::<Vec<()>>(&mut buf, config), Err(ByteBufError::LengthPrefixOverflow { .. })));
+        assert_eq!(buf.readable_bytes(), 8);
+    }
+
+    #[test]
+    fn test_derive_codec() {
+        use crate::codec::{Decode, Encode};
+
+        #[derive(Debug, PartialEq, bytebuf_derive::Encode, bytebuf_derive::Decode)]
+        #[bytebuf(tag = "u16_be")]
+        #[repr(u16)]
+        enum Command {
+            Ping = 1,
+            Say(#[bytebuf(len = "u8")] String),
+            Move { x: i16, #[bytebuf(le)] y: i16 },
+        }
+
+        fn magic() -> u32 {
+            0xcafe
+        }
+
+        #[derive(Debug, PartialEq, bytebuf_derive::Encode, bytebuf_derive::Decode)]
+        #[bytebuf(len = "varint")]
+        struct Packet {
+            id: u32,
+            #[bytebuf(le)]
+            seq: u64,
+            flag: bool,
+            key: [u8; 4],
+            name: String,
+            payload: Vec<u8>,
+            commands: Vec<Command>,
+            reply_to: Option<u16>,
+            #[bytebuf(skip)]
+            cached: usize,
+            #[bytebuf(default = "magic")]
+            magic: u32,
+        }
+
+        let packet = Packet {
+            id: 9,
+            seq: 2,
+            flag: true,
+            key: *b"abcd",
+            name: "n".to_string(),
+            payload: vec![7, 8],
+            commands: vec![Command::Ping, Command::Say("hi".to_string()), Command::Move { x: -1, y: 2 }],
+            reply_to: Some(3),
+            cached: 0,
+            magic: 0xcafe,
+        };
+
+        let mut buf = ByteBuf::new_with_capacity(0);
+        packet.encode(&mut buf).unwrap();
+
+        let mut manual = ByteBuf::new_with_capacity(0);
+        manual.write_u32_be(9).unwrap();
+        manual.write_u64_le(2).unwrap();
+        manual.write_bool(true).unwrap();
+        manual.write_bytes(b"abcd").unwrap();
+        manual.write_string_with_len(LengthPrefix::Varint, "n").unwrap();
+        manual.write_bytes_with_len(LengthPrefix::Varint, &[7, 8]).unwrap();
+        manual.write_varint_u32(3).unwrap();
+        manual.write_u16_be(1).unwrap();
+        manual.write_u16_be(2).unwrap();
+        manual.write_string_with_u8_be_len("hi".to_string()).unwrap();
+        manual.write_u16_be(3).unwrap();
+        manual.write_i16_be(-1).unwrap();
+        manual.write_i16_le(2).unwrap();
+        manual.write_u8_be(1).unwrap();
+        manual.write_u16_be(3).unwrap();
+        assert_eq!(buf.as_slice(), manual.as_slice());
+
+        assert_eq!(Packet::decode(&mut buf).unwrap(), packet);
+        assert!(!buf.is_readable());
+
+        // a truncated frame leaves the reader index where it was
+        let mut short = ByteBuf::new_from(&manual.as_slice()[..20]);
+        assert!(Packet::decode(&mut short).is_err());
+        assert_eq!(short.get_reader_index(), 0);
+
+        let mut unknown = ByteBuf::new_from(&[0, 9]);
+        assert!(Command::decode(&mut unknown).is_err());
+
+        // type parameters pick up the Encode/Decode bounds
+        #[derive(Debug, PartialEq, bytebuf_derive::Encode, bytebuf_derive::Decode)]
+        struct Tagged<T> {
+            tag: u8,
+            items: Vec<T>,
+        }
+
+        #[derive(Debug, PartialEq, bytebuf_derive::Encode, bytebuf_derive::Decode)]
+        struct Empty;
+
+        let tagged = Tagged { tag: 1, items: vec![Command::Ping, Command::Say("x".to_string())] };
+        let mut buf = ByteBuf::new_with_capacity(0);
+        tagged.encode(&mut buf).unwrap();
+        assert_eq!(Tagged::decode(&mut buf).unwrap(), tagged);
+
+        // a wire count of zero-width elements is bounded
+        let mut buf = ByteBuf::new_with_capacity(0);
+        Tagged { tag: 2, items: vec![Empty, Empty] }.encode(&mut buf).unwrap();
+        assert_eq!(Tagged::<Empty>::decode(&mut buf).unwrap().items.len(), 2);
+        buf.write_u8_be(3).unwrap();
+        buf.write_u32_be(u32::MAX).unwrap();
+        assert!(matches!(Tagged::<Empty>::decode(&mut buf), Err(ByteBufError::LengthPrefixOverflow { .. })));
+        assert_eq!(buf.readable_bytes(), 5);
+    }
+
+    #[test]
+    fn test_generic_accessors() {
+        use crate::bytebuf::{BigEndian, ByteOrder, FromBytes, LittleEndian, ToBytes, BE, LE};
+
+        #[derive(Debug, PartialEq)]
+        struct Point {
+            x: u16,
+            y: u16,
+        }
+
+        impl ToBytes for Point {
+            const SIZE: usize = 4;
+
+            fn to_bytes<E: ByteOrder>(&self, out: &mut [u8]) {
+                self.x.to_bytes::<E>(&mut out[..2]);
+                self.y.to_bytes::<E>(&mut out[2..]);
+            }
+        }
+
+        impl FromBytes for Point {
+            const SIZE: usize = 4;
+
+            fn from_bytes<E: ByteOrder>(bytes: &[u8]) -> Self {
+                Point { x: u16::from_bytes::<E>(&bytes[..2]), y: u16::from_bytes::<E>(&bytes[2..]) }
+            }
+        }
+
+        let mut buf = ByteBuf::new_with_capacity(0);
+        buf.write_as::<u32, BigEndian>(1).unwrap();
+        buf.write_as::<f64, LE>(2.5).unwrap();
+        buf.write_as::<Point, LittleEndian>(Point { x: 1, y: 2 }).unwrap();
+        buf.set_at::<i16, BE>(0, -2).unwrap();
+        assert_eq!(&buf.as_slice()[..4], &[0xff, 0xfe, 0, 1]);
+        assert_eq!(&buf.as_slice()[12..], &[1, 0, 2, 0]);
+
+        assert_eq!(buf.get_as::<u16, BE>(), 0xfffe);
+        assert_eq!(buf.read_as::<i32, BE>(), -131071);
+        assert_eq!(buf.read_f64_le(), 2.5);
+        assert_eq!(buf.try_read_as::<Point, LE>().unwrap(), Point { x: 1, y: 2 });
+        assert!(buf.try_read_as::<Point, LE>().is_err());
+    }
+
+    #[test]
+    fn test_runtime_endian() {
+        use crate::bytebuf::Endian;
+
+        let mut buf = ByteBuf::new_with_capacity(0);
+        assert_eq!(buf.endian(), Endian::Big);
+        buf.write_u32(1).unwrap();
+        buf.set_endian(Endian::Little);
+        buf.write_u32(1).unwrap();
+        buf.write_f64(0.5).unwrap();
+        buf.write_i16_ne(-7).unwrap();
+        buf.set_u16(0, 0x0102).unwrap();
+        assert_eq!(&buf.as_slice()[..8], &[2, 1, 0, 1, 1, 0, 0, 0]);
+
+        assert_eq!(buf.read_u16(), 0x0102);
+        assert_eq!(buf.read_with::<u16>(Endian::Big), 1);
+        assert_eq!(buf.try_read_u32().unwrap(), 1);
+        assert_eq!(buf.read_f64(), 0.5);
+        assert_eq!(buf.get_i16_ne(), -7);
+        assert_eq!(Endian::native() == Endian::Little, buf.get_i16_le() == -7);
+        assert!(buf.try_read_u32().is_err());
+    }
+
+    #[test]
+    fn test_slices() {
+        let mut buf = ByteBuf::new_with_capacity(0);
+        buf.write_u16_be(3).unwrap();
+        buf.write_bytes(b"abc").unwrap();
+        buf.write_u32_be(7).unwrap();
+
+        let len = buf.read_u16_be() as usize;
+        let mut frame = buf.read_slice(len).unwrap();
+        assert_eq!(buf.ref_count(), 2);
+        assert_eq!(frame.as_slice(), b"abc");
+        assert_eq!(frame.read_u8(), b'a');
+        assert_eq!(frame.readable_bytes(), 2);
+        assert_eq!(buf.get_u32_be(), 7);
+        assert!(buf.read_slice(5).is_err());
+
+        let rest = buf.retained_slice();
+        assert_eq!(rest.as_slice(), &[0, 0, 0, 7]);
+        assert_eq!(buf.slice(2, 3).unwrap().as_slice(), b"abc");
+        assert!(matches!(buf.slice(8, 2), Err(ByteBufError::IndexOutOfBounds { index: 8, len: 2, bound: 9 })));
+
+        let dup = buf.duplicate();
+        assert_eq!(dup.get_reader_index(), buf.get_reader_index());
+        drop(buf);
+        assert_eq!(rest.get_u32_be(), 7);
+
+        // writing to a shared view copies it first
+        frame.set_u8_be(0, b'x').unwrap();
+        frame.write_bytes(b"d").unwrap();
+        assert_eq!(frame.as_slice(), b"xbcd");
+        assert_eq!(dup.slice(2, 3).unwrap().as_slice(), b"abc");
+        assert_eq!(frame.ref_count(), 1);
+
+        // appending past live frames neither copies nor disturbs them
+        let mut buf = ByteBuf::new_with_capacity(64);
+        buf.write_bytes(b"onetwo").unwrap();
+        let one = buf.read_slice(3).unwrap();
+        let two = buf.read_slice(3).unwrap();
+        let before = buf.as_slice().as_ptr();
+        buf.write_bytes(b"three").unwrap();
+        buf.write_u8_be(b'!').unwrap();
+        assert_eq!(buf.as_slice().as_ptr(), before);
+        assert_eq!(buf.read_slice(6).unwrap().as_slice(), b"three!");
+        assert_eq!((one.as_slice(), two.as_slice()), (&b"one"[..], &b"two"[..]));
+
+        // while overwriting what they see copies the parent instead
+        buf.set_u8_be(0, b'O').unwrap();
+        assert_ne!(buf.as_slice().as_ptr(), before);
+        assert_eq!(&buf.as_slice()[..6], b"Onetwo");
+        assert_eq!(one.as_slice(), b"one");
+        assert_eq!(buf.ref_count(), 1);
+
+        // frames dropped on other threads while the owner keeps writing
+        let mut buf = ByteBuf::new_with_capacity(64);
+        buf.write_bytes(&[1u8; 32]).unwrap();
+        let readers: Vec<_> = (0..4).map(|_| {
+            let frame = buf.read_slice(8).unwrap();
+            std::thread::spawn(move || frame.as_slice().iter().map(|&b| b as usize).sum::<usize>())
+        }).collect();
+        // once they are gone the bytes they saw are overwritten in place
+        while buf.ref_count() > 1 {
+            std::thread::yield_now();
+        }
+        let before = buf.as_slice().as_ptr();
+        buf.set_u8_be(0, 9).unwrap();
+        assert_eq!(buf.as_slice().as_ptr(), before);
+        for _ in 0..8 {
+            buf.write_bytes(&[2u8; 4]).unwrap();
+        }
+        assert!(readers.into_iter().all(|reader| reader.join().unwrap() == 8));
+        buf.discard_read_bytes();
+        assert_eq!(buf.as_slice(), &[2u8; 32][..]);
+    }
+
+    #[test]
+    fn test_composite_bytebuf() {
+        let mut header = ByteBuf::new_with_capacity(0);
+        header.write_u16_be(0xCAFE).unwrap();
+        header.write_u8_be(0x01).unwrap();
+        let payload = ByteBuf::new_from(&[0x02, 0x03, 0x04, b'h', b'i']);
+
+        let mut buf = CompositeByteBuf::new();
+        buf.add_component(header);
+        buf.add_component(ByteBuf::new_with_capacity(0));
+        buf.add_component(payload);
+        assert_eq!(buf.num_components(), 2);
+        assert_eq!(buf.readable_bytes(), 8);
+
+        let mut out = Vec::new();
+        assert_eq!(buf.io_slices().len(), 2);
+        buf.clone().read_to_end(&mut out).unwrap();
+        assert_eq!(out, [0xCA, 0xFE, 0x01, 0x02, 0x03, 0x04, b'h', b'i']);
+
+        assert_eq!(buf.read_u16_be(), 0xCAFE);
+        buf.mark_reader_index();
+        assert_eq!(buf.get_u32_be(), 0x01020304);
+        assert!(buf.try_read_u64_le().is_err());
+        assert_eq!(buf.read_u32_le(), 0x04030201);
+        buf.discard_read_components();
+        assert_eq!(buf.num_components(), 1);
+        assert_eq!(buf.get_reader_index(), 3);
+        buf.reset_reader_index();
+        assert_eq!(buf.read_slice(1).unwrap().as_slice(), &[0x02]);
+
+        let mut sink = Vec::new();
+        buf.write_vectored_to(&mut sink).unwrap();
+        assert_eq!(sink, [0x03, 0x04, b'h', b'i']);
+        assert!(!buf.is_readable());
+
+        let mut buf = CompositeByteBuf::new();
+        buf.add_component(ByteBuf::new_from(b"ab"));
+        buf.add_component(ByteBuf::new_from(b"cd"));
+        buf.skip_index(1).unwrap();
+        assert_eq!(buf.clone().read_string(3), "bcd");
+        let mut flat = buf.into_bytebuf();
+        assert_eq!(flat.read_string(3), "bcd");
+
+        // prefixes, varints and strings split across components
+        let mut src = ByteBuf::new_with_capacity(0);
+        src.write_varint_u64(300).unwrap();
+        src.write_varint_i32(-5).unwrap();
+        src.write_string_with_len(LengthPrefix::U16Be, "héllo").unwrap();
+        src.write_string_with_len(LengthPrefix::Varint, "straddle").unwrap();
+        src.write_bytes_with_len(LengthPrefix::U8, &[1, 2, 3]).unwrap();
+        src.write_len(LengthPrefix::U32Le, 7).unwrap();
+        src.write_bytes(b"ab\xc3\xa9cd\xff").unwrap();
+        let mut buf = CompositeByteBuf::new();
+        for chunk in src.as_slice().chunks(3) {
+            buf.add_component(ByteBuf::new_from(chunk));
+        }
+
+        assert_eq!(buf.get_varint_u64(), 300);
+        assert_eq!(buf.read_varint_u32(), 300);
+        assert_eq!(buf.try_read_varint_i64().unwrap(), -5);
+        assert_eq!(buf.clone().read_string_with_u16_be_len(), "héllo");
+        assert_eq!(buf.read_string_with_u16_be_len_strict().unwrap(), "héllo");
+        assert_eq!(buf.read_string_with_len_strict(LengthPrefix::Varint).unwrap(), "straddle");
+        assert_eq!(buf.read_bytes_with_len(LengthPrefix::U8).unwrap(), [1, 2, 3]);
+        assert_eq!(buf.read_len(LengthPrefix::U32Le).unwrap(), 7);
+        assert_eq!(buf.try_read_string(1).unwrap(), "a");
+        assert!(matches!(buf.read_str_ref(3).unwrap(), std::borrow::Cow::Owned(s) if s == "bé"));
+        assert!(matches!(buf.read_str_ref(1).unwrap(), std::borrow::Cow::Borrowed("c")));
+        assert!(matches!(buf.read_string_strict(2), Err(ByteBufError::InvalidUtf8 { offset: 34 })));
+        assert!(buf.try_read_string(3).is_err());
+        assert_eq!(buf.get_reader_index(), 33);
+
+        let mut buf = CompositeByteBuf::new();
+        buf.add_component(ByteBuf::new_from(&[0x00]));
+        buf.add_component(ByteBuf::new_from(&[0x05, b'a']));
+        assert!(matches!(buf.try_read_string_with_u16_be_len(), Err(ByteBufError::InsufficientBytes { needed: 7, available: 3 })));
+        assert_eq!(buf.get_reader_index(), 0);
+        buf.add_component(ByteBuf::new_from(&[0x80]));
+        buf.skip_index(3).unwrap();
+        assert!(matches!(buf.try_get_varint_u64(), Err(ByteBufError::InsufficientBytes { needed: 2, available: 1 })));
+        assert!(buf.read_str_ref(1).is_err());
+        assert_eq!(buf.get_reader_index(), 3);
+
+        // runtime and native byte order
+        use crate::bytebuf::Endian;
+
+        let mut src = ByteBuf::new_with_capacity(0);
+        src.write_u32_be(1).unwrap();
+        src.write_u32_le(2).unwrap();
+        src.write_i16_ne(-7).unwrap();
+        src.write_f64_le(0.5).unwrap();
+        let mut buf = CompositeByteBuf::new();
+        for chunk in src.as_slice().chunks(3) {
+            buf.add_component(ByteBuf::new_from(chunk));
+        }
+        assert_eq!(buf.endian(), Endian::Big);
+        assert_eq!(buf.get_u32(), 1);
+        assert_eq!(buf.read_with::<u32>(Endian::Big), 1);
+        buf.set_endian(Endian::Little);
+        assert_eq!(buf.try_read_u32().unwrap(), 2);
+        assert_eq!(buf.get_i16_ne(), -7);
+        assert_eq!(buf.try_read_i16_ne().unwrap(), -7);
+        assert_eq!(buf.try_get_with::<f64>(Endian::Little).unwrap(), 0.5);
+        assert_eq!(buf.read_f64(), 0.5);
+        assert!(buf.try_read_u16().is_err());
+    }
+
+    #[test]
+    fn test_pool() {
+        use crate::bytebuf::pool::{self, PoolConfig, Unpooled};
+        use crate::bytebuf::{ByteBufAllocator, ByteBufPool};
+
+        let pool = ByteBufPool::with_config(PoolConfig { local_cache_size: 1, ..PoolConfig::default() });
+        let mut a = pool.buffer(1000);
+        assert_eq!(a.capacity(), 1024);
+        a.write_u32_be(1).unwrap();
+        a.mark_reader_index();
+        a.read_u16_be();
+        let b = pool.buffer(1000);
+        drop(a);
+        drop(b);
+        let stats = pool.stats();
+        assert_eq!((stats.hits, stats.misses), (0, 2));
+        assert_eq!(stats.bytes_retained, 2048);
+
+        // one comes from the thread cache, the other from the shared pool
+        let mut a = pool.buffer(600);
+        let b = pool.buffer(1024);
+        assert_eq!((a.readable_bytes(), a.get_reader_index()), (0, 0));
+        a.reset_reader_index();
+        assert_eq!(a.get_reader_index(), 0);
+        assert_eq!(pool.stats().hits, 2);
+        assert_eq!(pool.stats().bytes_retained, 0);
+
+        // buffers still shared by a slice, or kept, are not recycled
+        a.write_u16_be(7).unwrap();
+        let slice = a.slice(0, 2).unwrap();
+        drop(a);
+        let kept = b.into_inner();
+        assert_eq!(pool.stats().bytes_retained, 0);
+        assert_eq!(pool.stats().high_water_mark, 2048);
+        assert_eq!(slice.get_u16_be(), 7);
+        assert_eq!(kept.capacity(), 1024);
+
+        let big = pool.buffer(4 << 20);
+        drop(big);
+        assert_eq!(pool.stats().bytes_retained, 0);
+
+        let mut c = Unpooled.buffer(16);
+        c.write_u8_be(1).unwrap();
+        assert_eq!(c.read_u8(), 1);
+
+        // a pool dropped on another thread leaves no cache behind on this one
+        let (used, wait_used) = std::sync::mpsc::channel();
+        let (dropped, wait_dropped) = std::sync::mpsc::channel();
+        let worker = {
+            let pool = pool.clone();
+            std::thread::spawn(move || {
+                drop(pool.buffer(1000));
+                assert_eq!(pool::local_cache_count(), 1);
+                drop(pool);
+                used.send(()).unwrap();
+                wait_dropped.recv().unwrap();
+                let other = ByteBufPool::new();
+                drop(other.buffer(1000));
+                pool::local_cache_count()
+            })
+        };
+        wait_used.recv().unwrap();
+        drop(pool);
+        dropped.send(()).unwrap();
+        assert_eq!(worker.join().unwrap(), 1);
+    }
+
+    #[test]
+    fn test_capacity() {
+        use crate::bytebuf::GrowthStrategy;
+
+        let mut buf = ByteBuf::new_with_capacity(4);
+        buf.write_u32_be(1).unwrap();
+        assert!(!buf.is_writable());
+        buf.write_u8_be(2).unwrap();
+        assert_eq!(buf.capacity(), 8);
+        assert_eq!(buf.writable_bytes(), 3);
+
+        buf.set_growth_strategy(GrowthStrategy::Chunked);
+        buf.ensure_writable(10).unwrap();
+        assert_eq!(buf.capacity(), 1024);
+        buf.set_growth_strategy(GrowthStrategy::Exact);
+        buf.ensure_writable(2000).unwrap();
+        assert_eq!(buf.capacity(), 2005);
+        buf.set_growth_strategy(GrowthStrategy::Custom(|current, _| current + 100));
+        buf.ensure_writable(2001).unwrap();
+        assert_eq!(buf.capacity(), 2105);
+
+        buf.set_max_capacity(8).unwrap();
+        assert_eq!(buf.capacity(), 8);
+        assert_eq!(buf.max_writable_bytes(), 3);
+        buf.write_u16_be(3).unwrap();
+        assert_eq!(buf.write_u16_be(4).unwrap_err().kind(), std::io::ErrorKind::Other);
+        assert_eq!(buf.ensure_writable(2).unwrap_err(), ByteBufError::CapacityExceeded { required: 9, max: 8 });
+        assert_eq!(buf.get_writer_index(), 7);
+        assert!(buf.set_max_capacity(6).is_err());
+
+        // set_* stay below the writer index
+        assert!(buf.set_u16_be(6, 0).is_err());
+        buf.set_writer_index(8).unwrap();
+        buf.set_u16_be(6, 0x0102).unwrap();
+        assert_eq!(&buf.as_slice()[4..], &[2, 0, 1, 2]);
+    }
+
+    #[test]
+    fn test_hex_dump() {
+        let mut buf = ByteBuf::new_from(b"hello world\n");
+        buf.skip_index(2).unwrap();
+        assert_eq!(buf.hex_dump(), "\
+00000000  68 65 6c 6c 6f 20 77 6f  72 6c 64 0a              |hello world.|
+                ^r                             ^w
+0000000c
+");
+        assert_eq!(format!("{:x}", buf), "6c6c6f20776f726c640a");
+        assert_eq!(format!("{:#X}", buf.read_slice(3).unwrap()), "0x6C6C6F");
+        assert_eq!(buf.hex_dump_range(4, 3), "00000004  6f 20 77                                          |o w|\n             ^r\n00000007\n");
+
+        let mut buf = ByteBuf::new_with_capacity(0);
+        buf.write_bytes(&[0u8; 48]).unwrap();
+        buf.write_str("end").unwrap();
+        buf.skip_index(51).unwrap();
+        assert_eq!(buf.to_string(), "\
+00000000  00 00 00 00 00 00 00 00  00 00 00 00 00 00 00 00  |................|
+*
+00000030  65 6e 64                                          |end|
+                   ^rw
+00000033
+");
+    }
+
+    #[test]
+    fn test_text_encoding() {
+        use crate::bytebuf::Alphabet;
+
+        let mut buf = ByteBuf::new_from(b"foobar");
+        assert_eq!(buf.to_hex(), "666f6f626172");
+        assert_eq!(buf.to_base64(Alphabet::Standard), "Zm9vYmFy");
+        assert_eq!(buf.to_base32(), "MZXW6YTBOI======");
+        buf.skip_index(2).unwrap();
+        assert_eq!(buf.to_base64(Alphabet::Standard), "b2Jhcg==");
+        assert_eq!(buf.to_base64(Alphabet::StandardNoPad), "b2Jhcg");
+        assert_eq!(buf.to_base32(), "N5RGC4Q=");
+
+        assert_eq!(ByteBuf::from_hex("666F6f").unwrap().as_slice(), b"foo");
+        assert_eq!(ByteBuf::from_base64("b2Jhcg==", Alphabet::Standard).unwrap().as_slice(), b"obar");
+        assert_eq!(ByteBuf::from_base64("b2Jhcg", Alphabet::UrlSafe).unwrap().as_slice(), b"obar");
+        assert_eq!(ByteBuf::from_base32("mzxw6ytboi").unwrap().as_slice(), b"foobar");
+
+        let bytes = ByteBuf::new_from(&[0xfb, 0xff, 0xfe]);
+        assert_eq!(bytes.to_base64(Alphabet::Standard), "+//+");
+        assert_eq!(bytes.to_base64(Alphabet::UrlSafeNoPad), "-__-");
+        assert_eq!(ByteBuf::from_base64("-__-", Alphabet::UrlSafeNoPad).unwrap().as_slice(), &[0xfb, 0xff, 0xfe]);
+
+        assert_eq!(ByteBuf::from_hex("abc").unwrap_err(), ByteBufError::InvalidEncoding { encoding: "hex", offset: 3 });
+        assert_eq!(ByteBuf::from_hex("zz").unwrap_err(), ByteBufError::InvalidEncoding { encoding: "hex", offset: 0 });
+        assert!(ByteBuf::from_base64("b2Jhcg=", Alphabet::Standard).is_err());
+        assert!(ByteBuf::from_base64("b2Jhcg==", Alphabet::StandardNoPad).is_err());
+        assert!(ByteBuf::from_base64("+//+", Alphabet::UrlSafe).is_err());
+        assert!(ByteBuf::from_base32("MZXW6Y").is_err());
+
+        // padding longer than the last quantum needs
+        assert_eq!(ByteBuf::from_base64("QQ==", Alphabet::Standard).unwrap().as_slice(), b"A");
+        assert_eq!(ByteBuf::from_base64("QQ======", Alphabet::Standard).unwrap_err(),
+                   ByteBufError::InvalidEncoding { encoding: "base64", offset: 4 });
+        assert!(ByteBuf::from_base64("QUJD====", Alphabet::Standard).is_err());
+        assert!(ByteBuf::from_base64("QUI==", Alphabet::UrlSafe).is_err());
+        assert!(ByteBuf::from_base64("====", Alphabet::Standard).is_err());
+        assert_eq!(ByteBuf::from_base32("N5RGC4Q=").unwrap().as_slice(), b"obar");
+        assert_eq!(ByteBuf::from_base32("N5RGC4Q=========").unwrap_err(),
+                   ByteBufError::InvalidEncoding { encoding: "base32", offset: 8 });
+        assert!(ByteBuf::from_base32("MZXW6YTBOI==").is_err());
+        assert!(ByteBuf::from_base32("MZXW6YTB========").is_err());
+
+        let mut buf = ByteBuf::new_with_capacity(0);
+        assert_eq!(buf.write_hex_str("cafe01").unwrap(), 3);
+        assert!(buf.write_hex_str("cafe0").is_err());
+        assert_eq!(buf.read_hex_str(2).unwrap(), "cafe");
+        assert!(buf.read_hex_str(2).is_err());
+        assert_eq!(buf.read_hex_str(1).unwrap(), "01");
+    }
+
+    #[test]
+    fn test_length_field_frames() {
+        use crate::bytebuf::Endian;
+        use crate::codec::{FrameDecoder, LengthField, LengthFieldBasedFrameDecoder, LengthFieldPrepender};
+
+        // 2 byte magic, 3 byte length counting the whole frame, body
+        let mut decoder = LengthFieldBasedFrameDecoder::new(64, 2, LengthField::U24);
+        decoder.length_adjustment = -5;
+        decoder.initial_bytes_to_strip = 5;
+        let mut buf = ByteBuf::new_from(&[0xCA, 0xFE, 0, 0, 8, b'a', b'b']);
+        assert!(decoder.decode_frame(&mut buf).unwrap().is_none());
+        assert_eq!(buf.get_reader_index(), 0);
+        buf.write_bytes(&[b'c', 0xCA, 0xFE, 0, 0]).unwrap();
+        let frame = decoder.decode_frame(&mut buf).unwrap().unwrap();
+        assert_eq!(frame.as_slice(), b"abc");
+        assert!(decoder.decode_frame(&mut buf).unwrap().is_none());
+        buf.write_u8_be(200).unwrap();
+        assert_eq!(decoder.decode_frame(&mut buf).unwrap_err(), ByteBufError::FrameTooLong { len: 200, max: 64 });
+        buf.set_u8_be(12, 2).unwrap();
+        assert_eq!(decoder.decode_frame(&mut buf).unwrap_err(), ByteBufError::InvalidFrameLength { offset: 10 });
+
+        let mut prepender = LengthFieldPrepender::new(LengthField::U16);
+        prepender.endian = Endian::Little;
+        prepender.length_includes_length_field = true;
+        let mut out = ByteBuf::new_with_capacity(0);
+        prepender.encode(&ByteBuf::new_from(b"hello"), &mut out).unwrap();
+        assert_eq!(out.as_slice(), &[7, 0, b'h', b'e', b'l', b'l', b'o']);
+        let mut decoder = LengthFieldBasedFrameDecoder::new(64, 0, LengthField::U16);
+        decoder.endian = Endian::Little;
+        decoder.length_adjustment = -2;
+        assert_eq!(decoder.decode_frame(&mut out).unwrap().unwrap().as_slice(), &out.as_slice()[..7]);
+
+        let prepender = LengthFieldPrepender::new(LengthField::Varint);
+        let mut decoder = LengthFieldBasedFrameDecoder::new(1024, 0, LengthField::Varint);
+        decoder.initial_bytes_to_strip = 2;
+        let mut out = ByteBuf::new_with_capacity(0);
+        prepender.encode(&ByteBuf::from(vec![7u8; 300]), &mut out).unwrap();
+        assert_eq!(out.readable_bytes(), 302);
+        let mut partial = out.slice(0, 1).unwrap();
+        assert!(decoder.decode_frame(&mut partial).unwrap().is_none());
+        assert_eq!(decoder.decode_frame(&mut out).unwrap().unwrap().readable_bytes(), 300);
+
+        // a varint behind a header byte, with the reader index already moved
+        let mut decoder = LengthFieldBasedFrameDecoder::new(1024, 1, LengthField::Varint);
+        let mut buf = ByteBuf::new_from(&[0xEE, 0x01, 0x82]);
+        buf.skip_index(1).unwrap();
+        assert!(decoder.decode_frame(&mut buf).unwrap().is_none());
+        buf.write_bytes(&[0x00]).unwrap();
+        assert_eq!(decoder.decode_frame(&mut buf).unwrap_err(), ByteBufError::VarintOverlong { offset: 2 });
+        let mut buf = ByteBuf::new_from(&[0xEE, 0x01, 0x02, b'a', b'b']);
+        buf.skip_index(1).unwrap();
+        assert_eq!(decoder.decode_frame(&mut buf).unwrap().unwrap().as_slice(), &[0x01, 0x02, b'a', b'b']);
+
+        let prepender = LengthFieldPrepender::new(LengthField::U8);
+        assert!(prepender.encode(&ByteBuf::from(vec![0u8; 256]), &mut out).is_err());
+    }
+
+    #[test]
+    fn test_delimiter_frames() {
+        use crate::codec::{DelimiterBasedFrameDecoder, FrameDecoder, LineBasedFrameDecoder};
+
+        let mut decoder = LineBasedFrameDecoder::new(8);
+        let mut buf = ByteBuf::new_from(b"PING\r\nECHO hi\nSET");
+        assert_eq!(decoder.decode_frame(&mut buf).unwrap().unwrap().as_slice(), b"PING");
+        assert_eq!(decoder.decode_frame(&mut buf).unwrap().unwrap().as_slice(), b"ECHO hi");
+        assert!(decoder.decode_frame(&mut buf).unwrap().is_none());
+        assert_eq!(buf.readable_bytes(), 3);
+        buf.write_str(" key value\nGET\r").unwrap();
+        assert_eq!(decoder.decode_frame(&mut buf).unwrap_err(), ByteBufError::FrameTooLong { len: 13, max: 8 });
+        assert!(decoder.decode_frame(&mut buf).unwrap().is_none());
+        buf.write_str("\n").unwrap();
+        decoder.strip_delimiter = false;
+        assert_eq!(decoder.decode_frame(&mut buf).unwrap().unwrap().as_slice(), b"GET\r\n");
+
+        // a line that is too long is dropped up to its end
+        buf.write_str("0123456789").unwrap();
+        assert!(decoder.decode_frame(&mut buf).is_err());
+        buf.write_str("abc\nok\n").unwrap();
+        assert_eq!(decoder.decode_frame(&mut buf).unwrap().unwrap().as_slice(), b"ok\n");
+
+        let mut decoder = DelimiterBasedFrameDecoder::new(16, vec!["\0", "||"]);
+        let mut buf = ByteBuf::new_from(b"a||b\0c|");
+        assert_eq!(decoder.decode_frame(&mut buf).unwrap().unwrap().as_slice(), b"a");
+        assert_eq!(decoder.decode_frame(&mut buf).unwrap().unwrap().as_slice(), b"b");
+        assert!(decoder.decode_frame(&mut buf).unwrap().is_none());
+        assert_eq!(buf.get_reader_index(), 5);
+        buf.write_str("|").unwrap();
+        assert_eq!(decoder.decode_frame(&mut buf).unwrap().unwrap().as_slice(), b"c");
+        assert!(!buf.is_readable());
+    }
+
+    #[test]
+    fn test_search() {
+        use crate::bytebuf::search::{FIND_CRLF, FIND_NON_WHITESPACE, FIND_NUL};
+
+        let mut buf = ByteBuf::new_from(b"GET /index HTTP/1.1\r\nHost: a\0b");
+        buf.skip_index(4).unwrap();
+        assert_eq!(buf.index_of(0, 100, b'G'), None);
+        assert_eq!(buf.index_of(0, 100, b'/'), Some(4));
+        assert_eq!(buf.index_of(100, 0, b'/'), Some(15));
+        assert_eq!(buf.index_of(15, 4, b'/'), Some(4));
+        assert_eq!(buf.bytes_before(b' '), Some(6));
+        assert_eq!(buf.bytes_before(b'#'), None);
+
+        assert_eq!(buf.index_of_slice(b"HTTP"), Some(11));
+        assert_eq!(buf.index_of_slice(b"\r\nHost"), Some(19));
+        assert_eq!(buf.index_of_slice(b"GET"), None);
+        assert_eq!(buf.index_of_slice(b"b"), Some(29));
+        assert_eq!(buf.index_of_slice(b""), Some(4));
+
+        assert_eq!(buf.for_each_byte(.., FIND_CRLF), Some(19));
+        assert_eq!(buf.for_each_byte(.., FIND_NUL), Some(28));
+        assert_eq!(buf.for_each_byte(26.., FIND_NON_WHITESPACE), Some(27));
+        assert_eq!(buf.for_each_byte(..3, FIND_NUL), None);
+        let mut seen = 0;
+        assert_eq!(buf.for_each_byte(4..=6, |_| { seen += 1; true }), None);
+        assert_eq!(seen, 3);
+    }
+
+    #[test]
+    fn test_checksums() {
+        use crate::bytebuf::{ChecksumKind, Crc16};
+
+        let mut buf = ByteBuf::new_from(b"xx123456789");
+        buf.skip_index(2).unwrap();
+        assert_eq!(buf.crc32(..), 0xCBF43926);
+        assert_eq!(buf.crc32c(..), 0xE3069283);
+        assert_eq!(buf.crc16(.., Crc16::Ccitt), 0x29B1);
+        assert_eq!(buf.crc16(.., Crc16::Modbus), 0x4B37);
+        assert_eq!(buf.crc32(0..5), buf.crc32(2..5));
+        assert_eq!(ByteBuf::new_from(b"Wikipedia").adler32(..), 0x11E60398);
+        assert_eq!(ByteBuf::from(vec![0xffu8; 100_000]).adler32(..), 0x149A_302C);
+
+        buf.write_crc32_be_trailer().unwrap();
+        assert_eq!(buf.get_writer_index(), 15);
+        buf.verify_crc32_trailer().unwrap();
+        buf.set_u8_be(3, b'0').unwrap();
+        assert!(matches!(buf.verify_crc32_trailer(), Err(ByteBufError::ChecksumMismatch { expected: 0xCBF43926, .. })));
+
+        let mut out = ByteBuf::new_with_capacity(0);
+        out.set_discard_threshold(Some(0));
+        out.write_u8_be(0xAA).unwrap();
+        out.read_u8();
+        let mut writer = out.checksum_writer(ChecksumKind::Crc32);
+        writer.write_str("1234").unwrap();
+        write!(writer, "{}", 5).unwrap();
+        writer.write_u32_be(0x36373839).unwrap();
+        assert_eq!(writer.readable_bytes(), 9);
+        let crc = writer.value();
+        writer.write_u32_be(crc).unwrap();
+        drop(writer);
+        assert_eq!(crc, 0xCBF43926);
+        assert_eq!(out.discard_threshold(), Some(0));
+        assert_eq!(out.get_reader_index(), 1);
+        out.verify_crc32_trailer().unwrap();
+
+        let mut writer = out.checksum_writer(ChecksumKind::Crc32);
+        writer.write_bytes(b"1234").unwrap();
+        writer.reset();
+        writer.write_bytes(b"56789").unwrap();
+        assert_eq!(writer.value(), ByteBuf::new_from(b"56789").crc32(..));
+        drop(writer);
+
+        // every kind of write is folded in
+        use crate::bytebuf::{ChecksumWriter, Endian};
+
+        let check = |writer: &ChecksumWriter, len: usize| {
+            assert_eq!(writer.readable_bytes(), len);
+            assert_eq!(writer.value(), writer.crc32(..));
+        };
+        let mut out = ByteBuf::new_with_capacity(0);
+        out.set_endian(Endian::Little);
+        let mut writer = out.checksum_writer(ChecksumKind::Crc32);
+        writer.write_u32(1).unwrap();
+        check(&writer, 4);
+        writer.write_with(Endian::Big, 2u16).unwrap();
+        check(&writer, 6);
+        writer.write_f64_ne(0.5).unwrap();
+        check(&writer, 14);
+        writer.write_u8_le(3).unwrap();
+        writer.write_i8_le(-3).unwrap();
+        check(&writer, 16);
+        writer.write_string_with_u16_le_len("hi".to_string()).unwrap();
+        check(&writer, 20);
+        writer.write_string_with_len(LengthPrefix::Varint, "abc").unwrap();
+        check(&writer, 24);
+        writer.write_bytebuf(ByteBuf::new_from(b"xyz")).unwrap();
+        check(&writer, 27);
+        writer.write_hex_str("cafe").unwrap();
+        check(&writer, 29);
+        writer.write_varint_i64(-300).unwrap();
+        check(&writer, 31);
+        writer.write_as::<i128, crate::bytebuf::BE>(-1).unwrap();
+        check(&writer, 47);
+        assert!(writer.write_hex_str("abz").is_err());
+        check(&writer, 47);
+    }
+
+    #[test]
+    fn test_bits() {
+        use crate::bytebuf::BitOrder;
+
+        let mut buf = ByteBuf::new_with_capacity(0);
+        buf.write_u8_be(0xEE).unwrap();
+        let mut writer = buf.bit_writer(BitOrder::MsbFirst);
+        writer.write_bits(0b101, 3).unwrap();
+        assert_eq!(writer.bit_position(), 11);
+        writer.write_bits(0x1FFFF, 12).unwrap();
+        writer.write_bit(true).unwrap();
+        assert!(writer.is_aligned());
+        writer.write_bits(0b11, 2).unwrap();
+        assert_eq!(writer.align_to_byte(), 6);
+        writer.write_bits(u64::MAX, 64).unwrap();
+        for v in [0u64, 1, 2, 3, 7, 255, u64::MAX] {
+            writer.write_ue(v).unwrap();
+        }
+        for v in [0i64, 1, -1, 2, -2, i64::MAX, i64::MIN] {
+            writer.write_se(v).unwrap();
+        }
+        assert_eq!(&buf[..5], &[0xEE, 0xBF, 0xFF, 0xC0, 0xFF]);
+
+        buf.skip_index(1).unwrap();
+        let mut reader = buf.bit_reader(BitOrder::MsbFirst);
+        assert_eq!(reader.read_bits(3).unwrap(), 0b101);
+        assert_eq!(reader.bit_position(), 11);
+        assert_eq!(reader.read_bits(12).unwrap(), 0xFFF);
+        assert!(reader.read_bit().unwrap());
+        assert_eq!(reader.read_bits(2).unwrap(), 0b11);
+        assert_eq!(reader.align_to_byte(), 6);
+        assert_eq!(reader.read_bits(64).unwrap(), u64::MAX);
+        for v in [0u64, 1, 2, 3, 7, 255, u64::MAX] {
+            assert_eq!(reader.read_ue().unwrap(), v);
+        }
+        for v in [0i64, 1, -1, 2, -2, i64::MAX, i64::MIN] {
+            assert_eq!(reader.read_se().unwrap(), v);
+        }
+        let left = reader.bits_remaining();
+        assert!(left < 8);
+        assert!(matches!(reader.read_bits(8), Err(ByteBufError::InsufficientBytes { needed: 2, available: 1 })));
+        assert!(reader.read_ue().is_err());
+        assert_eq!(reader.bits_remaining(), left);
+
+        // ue(v) codes from the H.264 spec: 1, 010, 011, 00100
+        let mut reader = ByteBuf::new_from(&[0b1010_0110, 0b0100_0000]);
+        let mut reader = reader.bit_reader(BitOrder::MsbFirst);
+        assert_eq!((reader.read_ue().unwrap(), reader.read_ue().unwrap()), (0, 1));
+        assert_eq!((reader.read_ue().unwrap(), reader.read_ue().unwrap()), (2, 3));
+
+        let mut buf = ByteBuf::new_with_capacity(0);
+        let mut writer = buf.bit_writer(BitOrder::LsbFirst);
+        writer.write_bits(0b1, 1).unwrap();
+        writer.write_bits(0b01, 2).unwrap();
+        writer.write_bits(0x1ABC, 13).unwrap();
+        writer.write_bits(0x5, 4).unwrap();
+        assert_eq!(&buf[..], &[0b1110_0011, 0b1101_0101, 0x05]);
+        let mut reader = buf.bit_reader(BitOrder::LsbFirst);
+        assert_eq!(reader.read_bits(1).unwrap(), 1);
+        assert_eq!(reader.read_bits(2).unwrap(), 0b01);
+        assert_eq!(reader.read_bits(13).unwrap(), 0x1ABC);
+        assert_eq!(reader.read_bits(4).unwrap(), 0x5);
+        assert_eq!(buf.get_reader_index(), 2);
+    }
+}